@@ -2,6 +2,8 @@
 //!
 //! All of the `http_cache_semantics` logic is contained entirely within `fn make_a_request()`
 
+#![allow(clippy::needless_borrows_for_generic_args)]
+
 use std::{collections::HashMap, sync::{LazyLock, Mutex}, time::{Duration, SystemTime}};
 
 use dialoguer::{console::style, theme::ColorfulTheme, Input};
@@ -54,7 +56,7 @@ fn main() {
             .interact()
             .unwrap();
         match selection {
-            0 => make_a_request(&mut cache, cache_options),
+            0 => make_a_request(&mut cache, cache_options.clone()),
             1 => advance_time(),
             2 => list_cache_entries(&cache),
             3 => break,
@@ -69,45 +71,37 @@ fn main() {
 fn make_a_request(cache: &mut Cache, cache_options: CacheOptions) {
     use std::collections::hash_map::Entry;
 
-    use http_cache_semantics::{AfterResponse, BeforeRequest};
+    use http_cache_semantics::BeforeRequest;
 
     let req = setup_req();
     let resp = match cache.entry(req.uri().to_owned()) {
-        Entry::Occupied(mut occupied) => {
+        Entry::Occupied(occupied) => {
             let (policy, body) = occupied.get();
             match policy.before_request(&req, current_time()) {
                 BeforeRequest::Fresh(resp) => {
                     println!("{} retrieving cached response", bold("fresh cache entry!").green());
-                    Resp::from_parts(resp, body.to_owned())
+                    // NOTE: a 304 answers the client's conditional request, and MUST NOT have a body
+                    let body = if resp.status == http::StatusCode::NOT_MODIFIED { Body::new() } else { body.to_owned() };
+                    Resp::from_parts(resp, body)
+                },
+                BeforeRequest::StaleWhileRevalidate { response, revalidation_request } => {
+                    println!("{} serving cached response", bold("stale-while-revalidate!").yellow());
+                    let resp = Resp::from_parts(response, body.to_owned());
+                    // NOTE: a real cache would do this in the background
+                    revalidate(occupied, Req::from_parts(revalidation_request, ()));
+                    resp
                 },
                 BeforeRequest::Stale { request, .. } => {
                     println!("{}", bold("stale entry!").red());
-                    let new_req = Req::from_parts(request, ());
-                    let mut resp = server::get(new_req.clone());
-                    let after_resp = policy.after_response(&new_req, &resp, current_time());
-                    let (not_modified, new_policy, new_resp) = match after_resp {
-                        AfterResponse::NotModified(p, r) => (true, p, r),
-                        AfterResponse::Modified(p, r) => (false, p, r),
-                    };
-                    // NOTE: if the policy isn't storable then you MUST NOT store the entry
-                    if new_policy.is_storable() {
-                        if not_modified {
-                            println!("{} only updating metadata", bold("not modified!").blue());
-                            let entry = occupied.get_mut();
-                            entry.0 = new_policy;
-                            // and reconstruct the response from our cached bits
-                            resp = Resp::from_parts(new_resp, entry.1.clone());
-                        } else {
-                            println!("{} updating full entry", bold("modified!").magenta());
-                            occupied.insert((new_policy, resp.body().to_owned()));
-                        }
-                    } else {
-                        println!(
-                            "{} entry was not considered storable",
-                            bold("skipping cache!").red(),
-                        );
-                    }
-                    resp
+                    revalidate(occupied, Req::from_parts(request, ()))
+                }
+                BeforeRequest::GatewayTimeout(resp) => {
+                    println!("{} can't use the network", bold("only-if-cached!").red());
+                    Resp::from_parts(resp, Body::new())
+                }
+                _ => {
+                    println!("{}", bold("can't use the cache!").red());
+                    revalidate(occupied, req.clone())
                 }
             }
         }
@@ -134,6 +128,54 @@ fn make_a_request(cache: &mut Cache, cache_options: CacheOptions) {
     println!("{} {}\n", bold("body -").blue(), style(resp.body()).blue());
 }
 
+fn revalidate(mut occupied: std::collections::hash_map::OccupiedEntry<'_, Uri, (CachePolicy, Body)>, new_req: Req) -> Resp {
+    use http_cache_semantics::AfterResponse;
+
+    let policy = &occupied.get().0;
+    let mut resp = server::get(new_req.clone());
+    let after_resp = policy.after_response(&new_req, &resp, current_time());
+    let (not_modified, new_policy, new_resp) = match after_resp {
+        AfterResponse::NotModified(p, r) => (true, p, r),
+        AfterResponse::Modified(p, r) => (false, p, r),
+        AfterResponse::UpstreamError(Some(stale)) => {
+            println!("{} serving stale response", bold("server error!").red());
+            return Resp::from_parts(stale, occupied.get().1.clone());
+        }
+        AfterResponse::UpstreamError(None) => {
+            println!("{} passing on the error", bold("server error!").red());
+            return resp;
+        }
+        // only with `CacheOptions::partial_content` or `mark_incomplete()`, which this demo doesn't use
+        AfterResponse::Combined(..) => {
+            println!("{} passing on the partial response", bold("partial content!").yellow());
+            return resp;
+        }
+        _ => {
+            println!("{} passing on the response", bold("unknown outcome!").red());
+            return resp;
+        }
+    };
+    // NOTE: if the policy isn't storable then you MUST NOT store the entry
+    if new_policy.is_storable() {
+        if not_modified {
+            println!("{} only updating metadata", bold("not modified!").blue());
+            let entry = occupied.get_mut();
+            entry.0 = new_policy;
+            // and reconstruct the response from our cached bits
+            resp = Resp::from_parts(new_resp, entry.1.clone());
+        } else {
+            println!("{} updating full entry", bold("modified!").magenta());
+            occupied.insert((new_policy, resp.body().to_owned()));
+        }
+    } else {
+        println!(
+            "{} entry was not considered storable",
+            bold("skipping cache!").red(),
+        );
+    }
+    resp
+}
+
 fn advance_time() {
     let seconds: u64 = Input::with_theme(&*THEME)
         .with_prompt("seconds to advance")
@@ -197,6 +239,7 @@ mod helpers {
             ("/current-time",           "no-store"),
             ("/cached-current-time",    "max-age: 10s"),
             ("/friends-online",         "private, max-age: 30s"),
+            ("/headlines",              "max-age: 10s, stale-while-revalidate: 60s"),
             ("/user/123/profile-pic",   "e-tag w/ max-age: 30s"),
             ("/cache-busted-123B-8E2A", "immutable"),
         ];
@@ -242,6 +285,9 @@ mod server {
                     .header(header::CACHE_CONTROL, HeaderValue::from_static("private, max-age=30"))
                     .body(format!("{randomish_num} friends online"))
             }
+            "/headlines" => Response::builder()
+                .header(header::CACHE_CONTROL, HeaderValue::from_static("max-age=10, stale-while-revalidate=60"))
+                .body(format!("headlines as of {elapsed:?}")),
             "/user/123/profile-pic" => {
                 // picture that changes every 5 minutes
                 let maybe_client_e_tag = req.headers().get(header::IF_NONE_MATCH);
@@ -0,0 +1,757 @@
+//! `Cache-Control` header parsing and serialization ([RFC 9111 section 5.2](https://httpwg.org/specs/rfc9111.html#field.cache-control)).
+
+use http::HeaderValue;
+use std::fmt;
+use std::str::FromStr;
+use std::time::Duration;
+
+/// Directives with a meaning defined by RFC 9111, RFC 5861 or RFC 8246.
+/// Anything else is reported by [`CacheControl::extensions()`].
+const KNOWN_DIRECTIVES: &[&str] = &[
+    "immutable",
+    "max-age",
+    "max-stale",
+    "min-fresh",
+    "must-revalidate",
+    "must-understand",
+    "no-cache",
+    "no-store",
+    "no-transform",
+    "only-if-cached",
+    "private",
+    "proxy-revalidate",
+    "public",
+    "s-maxage",
+    "stale-if-error",
+    "stale-while-revalidate",
+];
+
+/// Directives whose arguments are field names, and should be sent as quoted-strings.
+const QUOTED_ARGUMENT_DIRECTIVES: &[&str] = &["no-cache", "private"];
+
+/// delta-seconds larger than this are treated as this value (RFC 9111 section 1.2.2)
+const MAX_DELTA_SECONDS: u64 = 1 << 31;
+
+/// Parsed `Cache-Control` header of a request or a response.
+///
+/// Directive names are case-insensitive, and are stored lowercased.
+/// The order of directives (including duplicates) is preserved,
+/// so that `to_string()` gives an equivalent header value.
+///
+/// ```rust
+/// use http_cache_semantics::CacheControl;
+/// use std::time::Duration;
+///
+/// let cc: CacheControl = r#"max-age=60, no-cache="set-cookie, x-foo""#.parse().unwrap();
+/// assert_eq!(cc.max_age(), Some(Duration::from_secs(60)));
+/// assert_eq!(cc.no_cache_fields().unwrap().collect::<Vec<_>>(), ["set-cookie", "x-foo"]);
+/// assert_eq!(cc.to_string(), r#"max-age=60, no-cache="set-cookie, x-foo""#);
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CacheControl {
+    directives: Vec<Directive>,
+}
+
+/// Lowercase name and unescaped argument
+type Directive = (Box<str>, Option<Box<str>>);
+
+/// Syntax error found by [`CacheControl::parse()`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum CacheControlParseError {
+    /// The header value is not valid ASCII/Latin-1 text
+    InvalidHeaderValue,
+    /// A character that isn't allowed at this position (byte offset)
+    UnexpectedCharacter(usize),
+    /// The directive has `=`, but no argument after it (byte offset)
+    MissingArgument(usize),
+    /// A quoted-string has no closing quote (byte offset of the opening quote)
+    UnterminatedQuotedString(usize),
+}
+
+impl fmt::Display for CacheControlParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Self::InvalidHeaderValue => f.write_str("Cache-Control is not a valid header value"),
+            Self::UnexpectedCharacter(pos) => write!(f, "Cache-Control has unexpected character at {pos}"),
+            Self::MissingArgument(pos) => write!(f, "Cache-Control directive is missing an argument at {pos}"),
+            Self::UnterminatedQuotedString(pos) => write!(f, "Cache-Control has unterminated quoted-string at {pos}"),
+        }
+    }
+}
+
+impl std::error::Error for CacheControlParseError {}
+
+impl CacheControl {
+    /// Empty set of directives
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Parses a single header value, following the `#cache-directive` grammar.
+    ///
+    /// Arguments can be tokens or quoted-strings (which may contain commas and escaped characters).
+    /// Empty list elements and whitespace around `=` are tolerated.
+    pub fn parse(value: &str) -> Result<Self, CacheControlParseError> {
+        let mut cc = Self::new();
+        Parser::new(value.as_bytes()).parse_list(&mut cc.directives)?;
+        Ok(cc)
+    }
+
+    /// Parses and combines all field lines of the header, in order.
+    ///
+    /// Fails on the first syntax error.
+    pub fn parse_header_values<'a>(values: impl IntoIterator<Item = &'a HeaderValue>) -> Result<Self, CacheControlParseError> {
+        let mut cc = Self::new();
+        for v in values {
+            let v = v.to_str().map_err(|_| CacheControlParseError::InvalidHeaderValue)?;
+            Parser::new(v.as_bytes()).parse_list(&mut cc.directives)?;
+        }
+        Ok(cc)
+    }
+
+    /// Like `parse_header_values`, but skips malformed directives instead of failing.
+    ///
+    /// This is how caches are expected to treat headers received from the network.
+    #[must_use]
+    pub fn parse_header_values_lossy<'a>(values: impl IntoIterator<Item = &'a HeaderValue>) -> Self {
+        let mut cc = Self::new();
+        for v in values.into_iter().filter_map(|v| v.to_str().ok()) {
+            Parser::new(v.as_bytes()).parse_list_lossy(&mut cc.directives);
+        }
+        cc
+    }
+
+    /// Parses a targeted cache control field, such as `CDN-Cache-Control` ([RFC 9213](https://httpwg.org/specs/rfc9213.html)).
+    ///
+    /// These fields use the Structured Fields Dictionary syntax ([RFC 8941](https://httpwg.org/specs/rfc8941.html)),
+    /// which is stricter than `Cache-Control`. Parameters are ignored, directives with a `false`
+    /// value are omitted, and the last occurrence of a directive wins.
+    pub fn parse_targeted(value: &str) -> Result<Self, CacheControlParseError> {
+        let mut cc = Self::new();
+        Parser::new(value.as_bytes()).parse_sf_dictionary(&mut cc)?;
+        Ok(cc)
+    }
+
+    /// Like `parse_targeted`, but for all field lines of the header
+    pub fn parse_targeted_header_values<'a>(values: impl IntoIterator<Item = &'a HeaderValue>) -> Result<Self, CacheControlParseError> {
+        let mut combined = String::new();
+        for v in values {
+            let v = v.to_str().map_err(|_| CacheControlParseError::InvalidHeaderValue)?;
+            if !combined.is_empty() {
+                combined.push_str(", ");
+            }
+            combined.push_str(v);
+        }
+        Self::parse_targeted(&combined)
+    }
+
+    /// `true` if there are no directives
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.directives.is_empty()
+    }
+
+    /// Whether the directive is present (with or without an argument)
+    #[must_use]
+    pub fn contains(&self, name: &str) -> bool {
+        self.directives.iter().any(|(k, _)| k.eq_ignore_ascii_case(name))
+    }
+
+    /// Argument of the first occurrence of the directive.
+    ///
+    /// Returns `None` if the directive is missing or has no argument.
+    /// Quoted-string arguments are returned unescaped.
+    #[must_use]
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.directives.iter()
+            .find(|(k, _)| k.eq_ignore_ascii_case(name))
+            .and_then(|(_, v)| v.as_deref())
+    }
+
+    /// All directives in order, with their unescaped arguments
+    pub fn iter(&self) -> impl Iterator<Item = (&str, Option<&str>)> {
+        self.directives.iter().map(|(k, v)| (&**k, v.as_deref()))
+    }
+
+    /// Directives that aren't defined by the HTTP caching RFCs
+    pub fn extensions(&self) -> impl Iterator<Item = (&str, Option<&str>)> {
+        self.iter().filter(|(k, _)| !KNOWN_DIRECTIVES.contains(k))
+    }
+
+    /// Sets the directive, replacing all previous occurrences of it.
+    ///
+    /// The argument is unescaped, and will be quoted when serialized if necessary.
+    pub fn insert(&mut self, name: &str, argument: Option<&str>) {
+        let name = name.to_ascii_lowercase();
+        let mut argument = argument.map(Box::from);
+        let mut found = false;
+        self.directives.retain_mut(|(k, v)| {
+            if **k != *name {
+                return true;
+            }
+            if found {
+                return false;
+            }
+            found = true;
+            *v = argument.take();
+            true
+        });
+        if !found {
+            self.directives.push((name.into(), argument));
+        }
+    }
+
+    /// Removes all occurrences of the directive. Returns `true` if it was present.
+    pub fn remove(&mut self, name: &str) -> bool {
+        let len = self.directives.len();
+        self.directives.retain(|(k, _)| !k.eq_ignore_ascii_case(name));
+        len != self.directives.len()
+    }
+
+    /// `true` if the same directive appears more than once with different arguments.
+    ///
+    /// RFC 9111 says such freshness information is invalid.
+    #[must_use]
+    pub fn has_conflicting_duplicates(&self) -> bool {
+        self.directives.iter().enumerate().any(|(i, (k, v))| {
+            self.directives[..i].iter().any(|(k2, v2)| k == k2 && v != v2)
+        })
+    }
+
+    /// Argument of a directive parsed as delta-seconds.
+    ///
+    /// `None` if the directive is missing or its argument is not a number.
+    #[must_use]
+    pub fn get_seconds(&self, name: &str) -> Option<Duration> {
+        self.get(name).and_then(parse_delta_seconds)
+    }
+
+    /// `max-age=N` request or response directive
+    #[must_use]
+    pub fn max_age(&self) -> Option<Duration> {
+        self.get_seconds("max-age")
+    }
+
+    /// `s-maxage=N` response directive
+    #[must_use]
+    pub fn s_maxage(&self) -> Option<Duration> {
+        self.get_seconds("s-maxage")
+    }
+
+    /// `max-stale[=N]` request directive.
+    ///
+    /// `Some(None)` means the client accepts a stale response of any age.
+    #[must_use]
+    pub fn max_stale(&self) -> Option<Option<Duration>> {
+        if !self.contains("max-stale") {
+            return None;
+        }
+        Some(self.get_seconds("max-stale"))
+    }
+
+    /// `min-fresh=N` request directive
+    #[must_use]
+    pub fn min_fresh(&self) -> Option<Duration> {
+        self.get_seconds("min-fresh")
+    }
+
+    /// `stale-while-revalidate=N` response directive (RFC 5861)
+    #[must_use]
+    pub fn stale_while_revalidate(&self) -> Option<Duration> {
+        self.get_seconds("stale-while-revalidate")
+    }
+
+    /// `stale-if-error=N` request or response directive (RFC 5861)
+    #[must_use]
+    pub fn stale_if_error(&self) -> Option<Duration> {
+        self.get_seconds("stale-if-error")
+    }
+
+    /// `no-cache` directive, qualified or not
+    #[must_use]
+    pub fn no_cache(&self) -> bool {
+        self.contains("no-cache")
+    }
+
+    /// Field names listed in a qualified `no-cache="…"` response directive.
+    ///
+    /// `None` if `no-cache` is missing or unqualified.
+    pub fn no_cache_fields(&self) -> Option<impl Iterator<Item = &str>> {
+        self.get("no-cache").map(field_names)
+    }
+
+    /// `private` directive, qualified or not
+    #[must_use]
+    pub fn private(&self) -> bool {
+        self.contains("private")
+    }
+
+    /// Field names listed in a qualified `private="…"` response directive.
+    ///
+    /// `None` if `private` is missing or unqualified.
+    pub fn private_fields(&self) -> Option<impl Iterator<Item = &str>> {
+        self.get("private").map(field_names)
+    }
+
+    /// `no-store` directive
+    #[must_use]
+    pub fn no_store(&self) -> bool {
+        self.contains("no-store")
+    }
+
+    /// `no-transform` directive
+    #[must_use]
+    pub fn no_transform(&self) -> bool {
+        self.contains("no-transform")
+    }
+
+    /// `public` response directive
+    #[must_use]
+    pub fn public(&self) -> bool {
+        self.contains("public")
+    }
+
+    /// `must-revalidate` response directive
+    #[must_use]
+    pub fn must_revalidate(&self) -> bool {
+        self.contains("must-revalidate")
+    }
+
+    /// `proxy-revalidate` response directive
+    #[must_use]
+    pub fn proxy_revalidate(&self) -> bool {
+        self.contains("proxy-revalidate")
+    }
+
+    /// `must-understand` response directive
+    #[must_use]
+    pub fn must_understand(&self) -> bool {
+        self.contains("must-understand")
+    }
+
+    /// `immutable` response directive (RFC 8246)
+    #[must_use]
+    pub fn immutable(&self) -> bool {
+        self.contains("immutable")
+    }
+
+    /// `only-if-cached` request directive
+    #[must_use]
+    pub fn only_if_cached(&self) -> bool {
+        self.contains("only-if-cached")
+    }
+
+    /// Serialized header value. Returns `None` if there are no directives,
+    /// since an empty `Cache-Control` header is pointless.
+    #[must_use]
+    pub fn to_header_value(&self) -> Option<HeaderValue> {
+        if self.is_empty() {
+            return None;
+        }
+        HeaderValue::from_str(&self.to_string()).ok()
+    }
+}
+
+impl FromStr for CacheControl {
+    type Err = CacheControlParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s)
+    }
+}
+
+impl fmt::Display for CacheControl {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, (k, v)) in self.directives.iter().enumerate() {
+            if i > 0 {
+                f.write_str(", ")?;
+            }
+            f.write_str(k)?;
+            if let Some(v) = v {
+                f.write_str("=")?;
+                let needs_quote = v.is_empty()
+                    || QUOTED_ARGUMENT_DIRECTIVES.contains(&&**k)
+                    || !v.bytes().all(is_tchar);
+                if needs_quote {
+                    f.write_str("\"")?;
+                    for ch in v.chars() {
+                        if ch == '"' || ch == '\\' {
+                            f.write_str("\\")?;
+                        }
+                        fmt::Write::write_char(f, ch)?;
+                    }
+                    f.write_str("\"")?;
+                } else {
+                    f.write_str(v)?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<'a> FromIterator<(&'a str, Option<&'a str>)> for CacheControl {
+    fn from_iter<T: IntoIterator<Item = (&'a str, Option<&'a str>)>>(iter: T) -> Self {
+        Self {
+            directives: iter.into_iter()
+                .map(|(k, v)| (k.to_ascii_lowercase().into(), v.map(Box::from)))
+                .collect(),
+        }
+    }
+}
+
+/// Serialized as a map of directive names to optional arguments,
+/// the same format that older versions of `CachePolicy` used.
+#[cfg(feature = "serde")]
+impl serde::Serialize for CacheControl {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeMap;
+        let mut map = serializer.serialize_map(Some(self.directives.len()))?;
+        for (k, v) in &self.directives {
+            map.serialize_entry(k, v)?;
+        }
+        map.end()
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for CacheControl {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct Visitor;
+        impl<'de> serde::de::Visitor<'de> for Visitor {
+            type Value = CacheControl;
+
+            fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str("a map of Cache-Control directives")
+            }
+
+            fn visit_map<A: serde::de::MapAccess<'de>>(self, mut map: A) -> Result<CacheControl, A::Error> {
+                let mut directives = Vec::with_capacity(map.size_hint().unwrap_or(0));
+                while let Some((k, v)) = map.next_entry::<Box<str>, Option<Box<str>>>()? {
+                    directives.push((k, v));
+                }
+                Ok(CacheControl { directives })
+            }
+        }
+        deserializer.deserialize_map(Visitor)
+    }
+}
+
+fn field_names(list: &str) -> impl Iterator<Item = &str> {
+    list.split(',').map(str::trim).filter(|f| !f.is_empty())
+}
+
+pub(crate) fn parse_delta_seconds(s: &str) -> Option<Duration> {
+    let s = s.trim();
+    if s.is_empty() || !s.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let secs = s.parse::<u64>().unwrap_or(MAX_DELTA_SECONDS);
+    Some(Duration::from_secs(secs.min(MAX_DELTA_SECONDS)))
+}
+
+fn is_tchar(b: u8) -> bool {
+    b.is_ascii_alphanumeric() || b"!#$%&'*+-.^_`|~".contains(&b)
+}
+
+struct Parser<'a> {
+    s: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn new(s: &'a [u8]) -> Self {
+        Self { s, pos: 0 }
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.s.get(self.pos).copied()
+    }
+
+    fn skip_ows(&mut self) {
+        while matches!(self.peek(), Some(b' ' | b'\t')) {
+            self.pos += 1;
+        }
+    }
+
+    fn parse_list(&mut self, out: &mut Vec<Directive>) -> Result<(), CacheControlParseError> {
+        while let Some(directive) = self.next_directive() {
+            out.push(directive?);
+        }
+        Ok(())
+    }
+
+    fn parse_list_lossy(&mut self, out: &mut Vec<Directive>) {
+        while let Some(directive) = self.next_directive() {
+            match directive {
+                Ok(d) => out.push(d),
+                Err(_) => self.skip_to_next_element(),
+            }
+        }
+    }
+
+    /// `None` at the end of input
+    fn next_directive(&mut self) -> Option<Result<Directive, CacheControlParseError>> {
+        loop {
+            self.skip_ows();
+            match self.peek()? {
+                b',' => self.pos += 1,
+                _ => return Some(self.parse_directive()),
+            }
+        }
+    }
+
+    fn parse_directive(&mut self) -> Result<Directive, CacheControlParseError> {
+        let name = self.parse_token().ok_or(CacheControlParseError::UnexpectedCharacter(self.pos))?;
+        let name = name.to_ascii_lowercase().into_boxed_str();
+        self.skip_ows();
+        let argument = if self.peek() == Some(b'=') {
+            self.pos += 1;
+            self.skip_ows();
+            let arg = if self.peek() == Some(b'"') {
+                self.parse_quoted_string()?
+            } else {
+                self.parse_token().ok_or(CacheControlParseError::MissingArgument(self.pos))?.into()
+            };
+            self.skip_ows();
+            Some(arg)
+        } else {
+            None
+        };
+        match self.peek() {
+            None | Some(b',') => Ok((name, argument)),
+            Some(_) => Err(CacheControlParseError::UnexpectedCharacter(self.pos)),
+        }
+    }
+
+    fn parse_token(&mut self) -> Option<&'a str> {
+        let start = self.pos;
+        while self.peek().is_some_and(is_tchar) {
+            self.pos += 1;
+        }
+        if start == self.pos {
+            return None;
+        }
+        std::str::from_utf8(&self.s[start..self.pos]).ok()
+    }
+
+    fn parse_quoted_string(&mut self) -> Result<Box<str>, CacheControlParseError> {
+        let start = self.pos;
+        debug_assert_eq!(self.peek(), Some(b'"'));
+        self.pos += 1;
+        let mut out = Vec::new();
+        loop {
+            match self.peek() {
+                None => return Err(CacheControlParseError::UnterminatedQuotedString(start)),
+                Some(b'"') => {
+                    self.pos += 1;
+                    break;
+                },
+                Some(b'\\') => {
+                    self.pos += 1;
+                    let escaped = self.peek().ok_or(CacheControlParseError::UnterminatedQuotedString(start))?;
+                    out.push(escaped);
+                    self.pos += 1;
+                },
+                Some(b) if b == b'\t' || b >= b' ' && b != 0x7F => {
+                    out.push(b);
+                    self.pos += 1;
+                },
+                Some(_) => return Err(CacheControlParseError::UnexpectedCharacter(self.pos)),
+            }
+        }
+        String::from_utf8(out)
+            .map(String::into_boxed_str)
+            .map_err(|_| CacheControlParseError::InvalidHeaderValue)
+    }
+
+    fn skip_to_next_element(&mut self) {
+        while let Some(b) = self.peek() {
+            match b {
+                b',' => return,
+                b'"' => {
+                    if self.parse_quoted_string().is_err() {
+                        self.pos = self.s.len();
+                    }
+                },
+                _ => self.pos += 1,
+            }
+        }
+    }
+}
+
+/// Value of a Structured Fields Dictionary member, as far as cache directives are concerned
+enum SfValue {
+    True,
+    False,
+    Argument(Box<str>),
+    /// Inner lists and byte sequences have no meaning for cache directives
+    Unsupported,
+}
+
+/// Structured Fields (RFC 8941) parsing for targeted cache control fields
+impl<'a> Parser<'a> {
+    fn skip_sp(&mut self) {
+        while self.peek() == Some(b' ') {
+            self.pos += 1;
+        }
+    }
+
+    fn unexpected(&self) -> CacheControlParseError {
+        CacheControlParseError::UnexpectedCharacter(self.pos)
+    }
+
+    fn parse_sf_dictionary(&mut self, out: &mut CacheControl) -> Result<(), CacheControlParseError> {
+        self.skip_sp();
+        if self.peek().is_none() {
+            return Ok(());
+        }
+        loop {
+            let key = self.parse_sf_key()?;
+            let value = if self.peek() == Some(b'=') {
+                self.pos += 1;
+                self.parse_sf_member_value()?
+            } else {
+                self.parse_sf_parameters()?;
+                SfValue::True
+            };
+            match value {
+                SfValue::True => out.insert(key, None),
+                SfValue::Argument(arg) => out.insert(key, Some(&arg)),
+                SfValue::False | SfValue::Unsupported => {
+                    out.remove(key);
+                },
+            }
+            self.skip_ows();
+            if self.peek().is_none() {
+                return Ok(());
+            }
+            if self.peek() != Some(b',') {
+                return Err(self.unexpected());
+            }
+            self.pos += 1;
+            self.skip_ows();
+            if self.peek().is_none() {
+                return Err(self.unexpected());
+            }
+        }
+    }
+
+    fn parse_sf_key(&mut self) -> Result<&'a str, CacheControlParseError> {
+        let start = self.pos;
+        if !self.peek().is_some_and(|b| b.is_ascii_lowercase() || b == b'*') {
+            return Err(self.unexpected());
+        }
+        while self.peek().is_some_and(|b| b.is_ascii_lowercase() || b.is_ascii_digit() || b"_-.*".contains(&b)) {
+            self.pos += 1;
+        }
+        // only ASCII has been consumed
+        Ok(std::str::from_utf8(&self.s[start..self.pos]).unwrap())
+    }
+
+    fn parse_sf_member_value(&mut self) -> Result<SfValue, CacheControlParseError> {
+        if self.peek() == Some(b'(') {
+            self.skip_sf_inner_list()?;
+            self.parse_sf_parameters()?;
+            return Ok(SfValue::Unsupported);
+        }
+        let value = self.parse_sf_bare_item()?;
+        self.parse_sf_parameters()?;
+        Ok(value)
+    }
+
+    fn skip_sf_inner_list(&mut self) -> Result<(), CacheControlParseError> {
+        self.pos += 1;
+        loop {
+            self.skip_sp();
+            if self.peek() == Some(b')') {
+                self.pos += 1;
+                return Ok(());
+            }
+            self.parse_sf_bare_item()?;
+            self.parse_sf_parameters()?;
+            if !matches!(self.peek(), Some(b' ' | b')')) {
+                return Err(self.unexpected());
+            }
+        }
+    }
+
+    fn parse_sf_parameters(&mut self) -> Result<(), CacheControlParseError> {
+        while self.peek() == Some(b';') {
+            self.pos += 1;
+            self.skip_sp();
+            self.parse_sf_key()?;
+            if self.peek() == Some(b'=') {
+                self.pos += 1;
+                self.parse_sf_bare_item()?;
+            }
+        }
+        Ok(())
+    }
+
+    fn parse_sf_bare_item(&mut self) -> Result<SfValue, CacheControlParseError> {
+        let start = self.pos;
+        match self.peek().ok_or(CacheControlParseError::MissingArgument(self.pos))? {
+            b'-' | b'0'..=b'9' => {
+                self.pos += 1;
+                while self.peek().is_some_and(|b| b.is_ascii_digit() || b == b'.') {
+                    self.pos += 1;
+                }
+            },
+            b'"' => {
+                self.pos += 1;
+                let mut out = String::new();
+                loop {
+                    match self.peek() {
+                        None => return Err(CacheControlParseError::UnterminatedQuotedString(start)),
+                        Some(b'"') => break,
+                        Some(b'\\') => {
+                            self.pos += 1;
+                            match self.peek() {
+                                Some(b @ (b'"' | b'\\')) => out.push(b as char),
+                                _ => return Err(self.unexpected()),
+                            }
+                        },
+                        Some(b @ 0x20..=0x7E) => out.push(b as char),
+                        Some(_) => return Err(self.unexpected()),
+                    }
+                    self.pos += 1;
+                }
+                self.pos += 1;
+                return Ok(SfValue::Argument(out.into()));
+            },
+            b'?' => {
+                self.pos += 1;
+                let value = match self.peek() {
+                    Some(b'0') => SfValue::False,
+                    Some(b'1') => SfValue::True,
+                    _ => return Err(self.unexpected()),
+                };
+                self.pos += 1;
+                return Ok(value);
+            },
+            b':' => {
+                self.pos += 1;
+                while self.peek().is_some_and(|b| b.is_ascii_alphanumeric() || b"+/=".contains(&b)) {
+                    self.pos += 1;
+                }
+                if self.peek() != Some(b':') {
+                    return Err(self.unexpected());
+                }
+                self.pos += 1;
+                return Ok(SfValue::Unsupported);
+            },
+            b if b.is_ascii_alphabetic() || b == b'*' => {
+                self.pos += 1;
+                while self.peek().is_some_and(|b| is_tchar(b) || b == b':' || b == b'/') {
+                    self.pos += 1;
+                }
+            },
+            _ => return Err(self.unexpected()),
+        }
+        // numbers and tokens are ASCII
+        Ok(SfValue::Argument(std::str::from_utf8(&self.s[start..self.pos]).unwrap().into()))
+    }
+}
@@ -0,0 +1,157 @@
+//! Human-readable explanation of `is_storable()` and `time_to_live()` results.
+
+use crate::Override;
+use http::Method;
+use http::StatusCode;
+use std::fmt;
+use std::time::Duration;
+
+/// A rule that can prevent a response from being stored or reused.
+///
+/// The `Display` implementation describes the condition that blocks caching.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum CacheabilityRule {
+    /// `Cache-Control: no-store` in the request
+    RequestNoStore,
+    /// The request method is not cacheable (only `GET`, `HEAD`, and `POST` with explicit freshness are)
+    MethodNotCacheable(Method),
+    /// The response status code is not understood by the cache
+    StatusNotUnderstood(StatusCode),
+    /// `Cache-Control: no-store` in the response
+    ResponseNoStore,
+    /// Unqualified `Cache-Control: private` in the response, and the cache is shared
+    PrivateInSharedCache,
+    /// `Authorization` in the request without `public`, `s-maxage` or `must-revalidate` in the response, and the cache is shared
+    AuthorizationInSharedCache,
+    /// No `Expires`, `max-age`, `s-maxage` or `public`, and the status code is not cacheable by default
+    NoFreshnessInformation,
+    /// Unqualified `Cache-Control: no-cache` (or `Pragma: no-cache`) in the response
+    NoCache,
+    /// `Set-Cookie` without `public` or `immutable`, and the cache is shared
+    SetCookieInSharedCache,
+    /// `Vary: *` never matches any request
+    VaryStar,
+    /// `Cache-Control: proxy-revalidate` in the response, and the cache is shared
+    ProxyRevalidate,
+    /// A response to a `HEAD` request had different validators or `Content-Length` (see `CachePolicy::after_head_response()`)
+    HeadResponseMismatch,
+    /// A `PolicyOverride` with this name doesn't allow storing the response
+    Override(String),
+}
+
+impl fmt::Display for CacheabilityRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::RequestNoStore => f.write_str("no-store in request"),
+            Self::MethodNotCacheable(method) => write!(f, "method {method} not cacheable"),
+            Self::StatusNotUnderstood(status) => write!(f, "status {} not understood", status.as_u16()),
+            Self::ResponseNoStore => f.write_str("no-store in response"),
+            Self::PrivateInSharedCache => f.write_str("private in shared cache"),
+            Self::AuthorizationInSharedCache => f.write_str("Authorization without public/s-maxage/must-revalidate"),
+            Self::NoFreshnessInformation => f.write_str("no explicit freshness and status not cacheable by default"),
+            Self::NoCache => f.write_str("no-cache in response"),
+            Self::SetCookieInSharedCache => f.write_str("Set-Cookie without public"),
+            Self::VaryStar => f.write_str("Vary: *"),
+            Self::ProxyRevalidate => f.write_str("proxy-revalidate in shared cache"),
+            Self::HeadResponseMismatch => f.write_str("HEAD response doesn't match"),
+            Self::Override(name) => write!(f, "not stored by override {name}"),
+        }
+    }
+}
+
+/// Result of evaluating one `CacheabilityRule`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RuleOutcome {
+    /// The rule that has been checked
+    pub rule: CacheabilityRule,
+    /// `true` if the rule's condition is met, so it prevents caching
+    pub applies: bool,
+}
+
+/// Where the freshness lifetime (`max-age` equivalent) of the response comes from
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum FreshnessSource {
+    /// `s-maxage` directive (shared caches only)
+    SMaxAge,
+    /// `max-age` directive
+    MaxAge,
+    /// `Expires` minus `Date`
+    Expires,
+    /// `cache_heuristic` fraction of `Date` minus `Last-Modified`
+    LastModifiedHeuristic,
+    /// `HeuristicFreshness::default_lifetime` for a response without `Last-Modified`
+    DefaultHeuristic,
+    /// `immutable_min_time_to_live` for `Cache-Control: immutable`
+    ImmutableMinimum,
+    /// `ttl` or `max_ttl` of a `PolicyOverride`
+    Override,
+    /// The response has no freshness information
+    None,
+}
+
+impl fmt::Display for FreshnessSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::SMaxAge => "s-maxage",
+            Self::MaxAge => "max-age",
+            Self::Expires => "Expires minus Date",
+            Self::LastModifiedHeuristic => "Last-Modified heuristic",
+            Self::DefaultHeuristic => "default heuristic lifetime",
+            Self::ImmutableMinimum => "immutable minimum",
+            Self::Override => "policy override",
+            Self::None => "no freshness information",
+        })
+    }
+}
+
+/// Explains why a response is (not) storable, and how long it stays fresh.
+///
+/// Returned by `CachePolicy::explain()`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CacheabilityReport {
+    /// Same as `is_storable()`
+    pub storable: bool,
+    /// Every rule checked by `is_storable()`, in order
+    pub storability_rules: Vec<RuleOutcome>,
+    /// Rules that make a stored response always stale, checked in order after storability
+    pub reuse_rules: Vec<RuleOutcome>,
+    /// Freshness lifetime after all rules and overrides have been applied.
+    ///
+    /// `time_to_live()` is this minus `age()`, or zero once the response is stale.
+    pub freshness_lifetime: Duration,
+    /// Where the freshness lifetime would come from, if no rule prevented reuse
+    pub freshness_source: FreshnessSource,
+    /// The `PolicyOverride` result applied to the response, if any
+    pub applied_override: Option<Override>,
+}
+
+impl CacheabilityReport {
+    /// The first rule that prevented storing or reusing the response.
+    ///
+    /// `None` if the response is storable and its freshness lifetime depends only on `freshness_source`.
+    #[must_use]
+    pub fn deciding_rule(&self) -> Option<&CacheabilityRule> {
+        self.storability_rules.iter()
+            .chain(&self.reuse_rules)
+            .find(|r| r.applies)
+            .map(|r| &r.rule)
+    }
+}
+
+impl fmt::Display for CacheabilityReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(r) = self.storability_rules.iter().find(|r| r.applies) {
+            return write!(f, "not storable: {}", r.rule);
+        }
+        writeln!(f, "storable")?;
+        if let Some(o) = &self.applied_override {
+            writeln!(f, "override: {}", o.name)?;
+        }
+        match self.reuse_rules.iter().find(|r| r.applies) {
+            Some(r) => write!(f, "always stale: {}", r.rule),
+            None => write!(f, "fresh for {}s from {}", self.freshness_lifetime.as_secs(), self.freshness_source),
+        }
+    }
+}
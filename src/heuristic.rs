@@ -0,0 +1,83 @@
+//! Heuristic freshness of responses without explicit expiration time ([RFC 9111 section 4.2.2](https://httpwg.org/specs/rfc9111.html#heuristic.freshness)).
+
+use http::StatusCode;
+use http::Uri;
+use std::time::Duration;
+
+/// How the freshness lifetime is guessed for responses that don't have `max-age` or `Expires`.
+/// Used in `CacheOptions::heuristic_freshness`.
+///
+/// By default the lifetime is `CacheOptions::cache_heuristic` fraction of the time since `Last-Modified`, without a limit.
+///
+/// Durations are serialized as whole seconds.
+///
+/// ```rust
+/// use http_cache_semantics::{CacheOptions, HeuristicFreshness};
+/// use std::time::Duration;
+///
+/// let options = CacheOptions {
+///     heuristic_freshness: HeuristicFreshness {
+///         max_lifetime: Some(Duration::from_secs(24 * 3600)),
+///         content_type_fractions: vec![("text/html".into(), 0.01), ("image/*".into(), 0.2)],
+///         skip_query: true,
+///         ..Default::default()
+///     },
+///     ..Default::default()
+/// };
+/// ```
+#[derive(Debug, Clone, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct HeuristicFreshness {
+    /// Upper limit of the heuristic freshness lifetime. `None` (default) is unlimited.
+    #[cfg_attr(feature = "serde", serde(with = "crate::secs"))]
+    pub max_lifetime: Option<Duration>,
+    /// Fractions of the time since `Last-Modified` for status classes, which take precedence over `cache_heuristic`.
+    /// The class is the first digit of the status code, e.g. `(4, 0.01)` is for all 4xx responses.
+    pub status_class_fractions: Vec<(u16, f32)>,
+    /// Fractions of the time since `Last-Modified` for media types in `Content-Type`, which take precedence over status classes.
+    /// Types can end with `/*` to match all subtypes, e.g. `image/*`. The first match is used.
+    pub content_type_fractions: Vec<(String, f32)>,
+    /// If `true`, responses for URIs with a query string don't get heuristic freshness,
+    /// since historically they've often been dynamic.
+    pub skip_query: bool,
+    /// Freshness lifetime of responses that don't have `Last-Modified` either.
+    /// `None` (default) doesn't give them any.
+    ///
+    /// It's used only for responses that can be stored without explicit freshness, i.e. status codes cacheable by default, or `public`.
+    #[cfg_attr(feature = "serde", serde(with = "crate::secs"))]
+    pub default_lifetime: Option<Duration>,
+}
+
+impl HeuristicFreshness {
+    /// Whether the heuristic can be used for the URI at all
+    pub(crate) fn applies_to(&self, uri: &Uri) -> bool {
+        !(self.skip_query && uri.query().is_some())
+    }
+
+    /// Fraction of the time since `Last-Modified`, `default_fraction` if no rule matches
+    pub(crate) fn fraction(&self, default_fraction: f32, status: StatusCode, content_type: Option<&str>) -> f32 {
+        let media_type = content_type
+            .and_then(|t| t.split(';').next())
+            .map(|t| t.trim().to_ascii_lowercase());
+        if let Some(media_type) = media_type {
+            let by_type = self.content_type_fractions.iter().find(|(pattern, _)| {
+                match pattern.strip_suffix('*') {
+                    Some(prefix) => media_type.starts_with(&prefix.to_ascii_lowercase()),
+                    None => pattern.eq_ignore_ascii_case(&media_type),
+                }
+            });
+            if let Some(&(_, fraction)) = by_type {
+                return fraction;
+            }
+        }
+        self.status_class_fractions.iter()
+            .find(|&&(class, _)| class == status.as_u16() / 100)
+            .map_or(default_fraction, |&(_, fraction)| fraction)
+    }
+
+    /// The lifetime within `max_lifetime`
+    pub(crate) fn limit(&self, lifetime: Duration) -> Duration {
+        self.max_lifetime.map_or(lifetime, |max| lifetime.min(max))
+    }
+}
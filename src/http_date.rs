@@ -0,0 +1,253 @@
+//! `HTTP-date` parsing and formatting ([RFC 9110 section 5.6.7](https://httpwg.org/specs/rfc9110.html#http.date)).
+
+use std::fmt;
+use std::str::FromStr;
+use std::time::Duration;
+use std::time::SystemTime;
+use time::format_description::well_known::Rfc2822;
+use time::Date;
+use time::Month;
+use time::OffsetDateTime;
+use time::PrimitiveDateTime;
+use time::Time;
+
+const DAY_NAMES: [&str; 7] = ["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"];
+const DAY_NAMES_LONG: [&str; 7] = ["Monday", "Tuesday", "Wednesday", "Thursday", "Friday", "Saturday", "Sunday"];
+const MONTH_NAMES: [&str; 12] = ["Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec"];
+
+/// 0000-01-01T00:00:00Z, the earliest date with a 4-digit year
+const MIN_UNIX_TIME: i64 = -62_167_219_200;
+/// 9999-12-31T23:59:59Z, the latest date with a 4-digit year
+const MAX_UNIX_TIME: i64 = 253_402_300_799;
+
+/// A timestamp in the format used by `Date`, `Expires`, `Last-Modified` and other headers.
+///
+/// Parsing accepts all three formats required by RFC 9110: the preferred IMF-fixdate
+/// (`Sun, 06 Nov 1994 08:49:37 GMT`), the obsolete RFC 850 format (`Sunday, 06-Nov-94 08:49:37 GMT`)
+/// and ANSI C's asctime format (`Sun Nov  6 08:49:37 1994`). Other RFC 2822 dates
+/// (e.g. with a `+0000` zone) are accepted too, for compatibility with older versions of this crate.
+///
+/// Formatting always uses IMF-fixdate. The precision is one second.
+///
+/// ```rust
+/// use http_cache_semantics::HttpDate;
+///
+/// let date: HttpDate = "Sunday, 06-Nov-94 08:49:37 GMT".parse().unwrap();
+/// assert_eq!(date, HttpDate::parse("Sun Nov  6 08:49:37 1994").unwrap());
+/// assert_eq!(date.to_string(), "Sun, 06 Nov 1994 08:49:37 GMT");
+/// ```
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct HttpDate {
+    unix_time: i64,
+}
+
+/// The value is not a valid `HTTP-date`
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct HttpDateParseError;
+
+impl fmt::Display for HttpDateParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("invalid HTTP-date")
+    }
+}
+
+impl std::error::Error for HttpDateParseError {}
+
+impl HttpDate {
+    /// Parses any of the `HTTP-date` formats.
+    ///
+    /// Two-digit years of the RFC 850 format that would be more than 50 years
+    /// in the future are interpreted as being in the past century.
+    pub fn parse(value: &str) -> Result<Self, HttpDateParseError> {
+        Self::parse_relative_to(value, SystemTime::now())
+    }
+
+    /// Like `parse()`, but the two-digit years of the RFC 850 format are relative to the given `now` rather than the system clock
+    pub fn parse_relative_to(value: &str, now: SystemTime) -> Result<Self, HttpDateParseError> {
+        let value = value.trim_matches([' ', '\t']);
+        let mut p = Parser { s: value.as_bytes() };
+        let unix_time = if value.as_bytes().get(3) == Some(&b',') {
+            p.imf_fixdate()
+        } else if value.contains(',') {
+            p.rfc850_date(Self::from(now).year())
+        } else {
+            p.asctime_date()
+        };
+        unix_time
+            .or_else(|| OffsetDateTime::parse(value, &Rfc2822).ok().map(|d| d.unix_timestamp()))
+            .filter(|t| (MIN_UNIX_TIME..=MAX_UNIX_TIME).contains(t))
+            .map(|unix_time| Self { unix_time })
+            .ok_or(HttpDateParseError)
+    }
+
+    /// Seconds since 1970-01-01T00:00:00Z
+    #[must_use]
+    pub fn unix_timestamp(&self) -> i64 {
+        self.unix_time
+    }
+
+    /// The date for seconds since 1970-01-01T00:00:00Z, or `None` if the year wouldn't have 4 digits
+    #[must_use]
+    pub fn from_unix_timestamp(unix_time: i64) -> Option<Self> {
+        (MIN_UNIX_TIME..=MAX_UNIX_TIME).contains(&unix_time).then_some(Self { unix_time })
+    }
+
+    fn to_date_time(self) -> OffsetDateTime {
+        OffsetDateTime::from_unix_timestamp(self.unix_time).unwrap()
+    }
+
+    fn year(self) -> i32 {
+        self.to_date_time().year()
+    }
+}
+
+/// The time is truncated to whole seconds, and clamped to years 0000-9999.
+impl From<SystemTime> for HttpDate {
+    fn from(time: SystemTime) -> Self {
+        let unix_time = match time.duration_since(SystemTime::UNIX_EPOCH) {
+            Ok(after) => i64::try_from(after.as_secs()).unwrap_or(i64::MAX),
+            Err(before) => i64::try_from(before.duration().as_secs()).map_or(i64::MIN, |s| -s),
+        };
+        Self { unix_time: unix_time.clamp(MIN_UNIX_TIME, MAX_UNIX_TIME) }
+    }
+}
+
+impl From<HttpDate> for SystemTime {
+    fn from(date: HttpDate) -> Self {
+        let secs = Duration::from_secs(date.unix_time.unsigned_abs());
+        if date.unix_time >= 0 {
+            Self::UNIX_EPOCH + secs
+        } else {
+            Self::UNIX_EPOCH.checked_sub(secs).unwrap_or(Self::UNIX_EPOCH)
+        }
+    }
+}
+
+impl FromStr for HttpDate {
+    type Err = HttpDateParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s)
+    }
+}
+
+/// Formats as IMF-fixdate, e.g. `Sun, 06 Nov 1994 08:49:37 GMT`
+impl fmt::Display for HttpDate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let d = self.to_date_time();
+        write!(f, "{}, {:02} {} {:04} {:02}:{:02}:{:02} GMT",
+            DAY_NAMES[d.weekday().number_days_from_monday() as usize],
+            d.day(),
+            MONTH_NAMES[d.month() as usize - 1],
+            d.year(),
+            d.hour(),
+            d.minute(),
+            d.second(),
+        )
+    }
+}
+
+struct Parser<'a> {
+    s: &'a [u8],
+}
+
+impl Parser<'_> {
+    /// `Sun, 06 Nov 1994 08:49:37 GMT`
+    fn imf_fixdate(&mut self) -> Option<i64> {
+        self.one_of(&DAY_NAMES)?;
+        self.tag(", ")?;
+        let day = self.digits(2)?;
+        self.tag(" ")?;
+        let month = self.month()?;
+        self.tag(" ")?;
+        let year = self.digits(4)?;
+        self.tag(" ")?;
+        let time = self.time_of_day()?;
+        self.tag(" GMT")?;
+        self.finish(year as i32, month, day, time)
+    }
+
+    /// `Sunday, 06-Nov-94 08:49:37 GMT`
+    fn rfc850_date(&mut self, current_year: i32) -> Option<i64> {
+        self.one_of(&DAY_NAMES_LONG)?;
+        self.tag(", ")?;
+        let day = self.digits(2)?;
+        self.tag("-")?;
+        let month = self.month()?;
+        self.tag("-")?;
+        let two_digit_year = self.digits(2)? as i32;
+        self.tag(" ")?;
+        let time = self.time_of_day()?;
+        self.tag(" GMT")?;
+
+        // Recipients of a timestamp value in rfc850-date format, which uses a two-digit year, MUST interpret a timestamp
+        // that appears to be more than 50 years in the future as representing the most recent year in the past that had the same last two digits.
+        let mut year = current_year - current_year.rem_euclid(100) + two_digit_year;
+        if year > current_year + 50 {
+            year -= 100;
+        } else if year <= current_year - 50 {
+            year += 100;
+        }
+        self.finish(year, month, day, time)
+    }
+
+    /// `Sun Nov  6 08:49:37 1994`
+    fn asctime_date(&mut self) -> Option<i64> {
+        self.one_of(&DAY_NAMES)?;
+        self.tag(" ")?;
+        let month = self.month()?;
+        self.tag(" ")?;
+        let day = if self.tag(" ").is_some() { self.digits(1)? } else { self.digits(2)? };
+        self.tag(" ")?;
+        let time = self.time_of_day()?;
+        self.tag(" ")?;
+        let year = self.digits(4)?;
+        self.finish(year as i32, month, day, time)
+    }
+
+    fn finish(&self, year: i32, month: Month, day: u32, (hour, minute, second): (u32, u32, u32)) -> Option<i64> {
+        if !self.s.is_empty() {
+            return None;
+        }
+        let date = Date::from_calendar_date(year, month, day.try_into().ok()?).ok()?;
+        // a leap second can't be represented, but is still a valid date
+        let time = Time::from_hms(hour.try_into().ok()?, minute.try_into().ok()?, second.min(59).try_into().ok()?).ok()?;
+        Some(PrimitiveDateTime::new(date, time).assume_utc().unix_timestamp())
+    }
+
+    fn time_of_day(&mut self) -> Option<(u32, u32, u32)> {
+        let hour = self.digits(2)?;
+        self.tag(":")?;
+        let minute = self.digits(2)?;
+        self.tag(":")?;
+        let second = self.digits(2)?;
+        (second <= 60).then_some((hour, minute, second))
+    }
+
+    fn month(&mut self) -> Option<Month> {
+        let n = self.one_of(&MONTH_NAMES)?;
+        Month::try_from(n as u8 + 1).ok()
+    }
+
+    /// Index of the name that matches (case-sensitively)
+    fn one_of(&mut self, names: &[&str]) -> Option<usize> {
+        let n = names.iter().position(|name| self.s.starts_with(name.as_bytes()))?;
+        self.s = &self.s[names[n].len()..];
+        Some(n)
+    }
+
+    fn tag(&mut self, tag: &str) -> Option<()> {
+        self.s = self.s.strip_prefix(tag.as_bytes())?;
+        Some(())
+    }
+
+    fn digits(&mut self, len: usize) -> Option<u32> {
+        let digits = self.s.get(..len)?;
+        if !digits.iter().all(u8::is_ascii_digit) {
+            return None;
+        }
+        self.s = &self.s[len..];
+        Some(digits.iter().fold(0, |n, &d| n * 10 + u32::from(d - b'0')))
+    }
+}
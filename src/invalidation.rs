@@ -0,0 +1,179 @@
+//! Invalidation of stored responses after unsafe requests ([RFC 9111 section 4.4](https://httpwg.org/specs/rfc9111.html#invalidation)).
+
+use crate::GetHeaderStr;
+use crate::RequestLike;
+use crate::ResponseLike;
+use http::uri::Authority;
+use http::uri::Scheme;
+use http::Uri;
+
+/// URIs of stored responses that have to be invalidated after an unsafe request
+/// (such as `POST`, `PUT`, `DELETE` or `PATCH`) got a non-error response.
+///
+/// These are the request's URI, and the URIs in `Location` and `Content-Location` response headers,
+/// resolved against the request's URI. URIs that don't have the same origin as the request are skipped,
+/// because a server could otherwise wipe another server's responses from the cache.
+///
+/// If the request's URI is only a path (origin-form), the `Host` header is used as its origin,
+/// and all returned URIs are paths too.
+///
+/// It's empty for safe methods, and for responses with 1xx, 4xx or 5xx status codes.
+///
+/// ```rust
+/// use http::{Method, Request, Response};
+///
+/// let req = Request::builder().method(Method::POST).uri("https://example.com/items/").body(()).unwrap();
+/// let res = Response::builder().status(201).header("location", "42").body(()).unwrap();
+/// let targets = http_cache_semantics::invalidation_targets(&req, &res);
+/// assert_eq!(targets, ["https://example.com/items/", "https://example.com/items/42"]);
+/// ```
+pub fn invalidation_targets<Req: RequestLike, Res: ResponseLike>(req: &Req, res: &Res) -> Vec<Uri> {
+    // A cache MUST invalidate the target URI when it receives a non-error status code
+    // in response to an unsafe request method (including methods whose safety is unknown).
+    let status = res.status();
+    if req.method().is_safe() || !(status.is_success() || status.is_redirection()) {
+        return Vec::new();
+    }
+
+    let base = req.uri();
+    let host = req.headers().get_str("host");
+    let mut targets = vec![base.clone()];
+    for name in ["location", "content-location"] {
+        let Some(reference) = res.headers().get_str(name) else {
+            continue;
+        };
+        // a cache MUST NOT trigger an invalidation under these conditions if the origin
+        // of the URI to be invalidated differs from that of the target URI
+        if let Some(uri) = resolve(&base, host, reference.trim()) {
+            if !targets.contains(&uri) {
+                targets.push(uri);
+            }
+        }
+    }
+    targets
+}
+
+/// Whether `a` and `b` identify the same resource, using `Host` headers for origin-form URIs
+pub(crate) fn is_same_resource(a: &Uri, a_host: Option<&str>, b: &Uri, b_host: Option<&str>) -> bool {
+    if a.path_and_query().map(|p| p.as_str()) != b.path_and_query().map(|p| p.as_str()) {
+        return false;
+    }
+    if let (Some(a), Some(b)) = (a.scheme(), b.scheme()) {
+        if a != b {
+            return false;
+        }
+    }
+    let a_authority = a.authority().map(Authority::as_str).or(a_host);
+    let b_authority = b.authority().map(Authority::as_str).or(b_host);
+    match (a_authority, b_authority) {
+        (Some(a_authority), Some(b_authority)) => {
+            let scheme = a.scheme().or(b.scheme());
+            same_authority(a_authority, b_authority, scheme)
+        },
+        (a_authority, b_authority) => a_authority == b_authority,
+    }
+}
+
+/// Resolves a URI reference (RFC 3986 section 5.2) that has the same origin as the `base`
+fn resolve(base: &Uri, host: Option<&str>, reference: &str) -> Option<Uri> {
+    let reference = reference.split('#').next().unwrap_or_default();
+    let base_path = base.path();
+
+    let (authority, path_and_query) = if has_scheme(reference) {
+        let uri: Uri = reference.parse().ok()?;
+        let base_authority = base.authority().map(Authority::as_str).or(host)?;
+        if base.scheme().is_some_and(|s| Some(s) != uri.scheme())
+            || !same_authority(uri.authority()?.as_str(), base_authority, uri.scheme())
+        {
+            return None;
+        }
+        (uri.authority()?.as_str().to_owned(), uri.path_and_query().map_or("/", |p| p.as_str()).to_owned())
+    } else if let Some(network_path) = reference.strip_prefix("//") {
+        let uri: Uri = format!("http://{network_path}").parse().ok()?;
+        let base_authority = base.authority().map(Authority::as_str).or(host)?;
+        if !same_authority(uri.authority()?.as_str(), base_authority, base.scheme()) {
+            return None;
+        }
+        (uri.authority()?.as_str().to_owned(), uri.path_and_query().map_or("/", |p| p.as_str()).to_owned())
+    } else {
+        let (path, query) = match reference.split_once('?') {
+            Some((path, query)) => (path, Some(query)),
+            None => (reference, None),
+        };
+        let path = if path.is_empty() {
+            base_path.to_owned()
+        } else if path.starts_with('/') {
+            remove_dot_segments(path)
+        } else {
+            // merge with the base path, without its last segment
+            let dir = &base_path[..base_path.rfind('/').map_or(0, |i| i + 1)];
+            remove_dot_segments(&format!("{}{path}", if dir.is_empty() { "/" } else { dir }))
+        };
+        let query = match query {
+            Some(query) => Some(query),
+            None if reference.is_empty() => base.query(),
+            None => None,
+        };
+        let path_and_query = match query {
+            Some(query) => format!("{path}?{query}"),
+            None => path,
+        };
+        (base.authority().map(|a| a.as_str().to_owned()).unwrap_or_default(), path_and_query)
+    };
+
+    match (base.scheme(), base.authority()) {
+        (Some(scheme), Some(_)) => Uri::builder()
+            .scheme(scheme.clone())
+            .authority(authority)
+            .path_and_query(path_and_query)
+            .build()
+            .ok(),
+        // origin-form request target
+        _ => path_and_query.parse().ok(),
+    }
+}
+
+fn has_scheme(reference: &str) -> bool {
+    let Some((scheme, _)) = reference.split_once(':') else {
+        return false;
+    };
+    scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+        && scheme.bytes().all(|c| c.is_ascii_alphanumeric() || matches!(c, b'+' | b'-' | b'.'))
+}
+
+/// Case-insensitive host comparison, with the default port of the scheme filled in
+fn same_authority(a: &str, b: &str, scheme: Option<&Scheme>) -> bool {
+    let (Ok(a), Ok(b)) = (a.parse::<Authority>(), b.parse::<Authority>()) else {
+        return false;
+    };
+    let default_port = match scheme.map(Scheme::as_str) {
+        Some(s) if s.eq_ignore_ascii_case("https") => Some(443),
+        Some(s) if s.eq_ignore_ascii_case("http") => Some(80),
+        _ => None,
+    };
+    a.host().eq_ignore_ascii_case(b.host()) && a.port_u16().or(default_port) == b.port_u16().or(default_port)
+}
+
+/// RFC 3986 section 5.2.4
+fn remove_dot_segments(path: &str) -> String {
+    let mut output: Vec<&str> = Vec::new();
+    let segments: Vec<&str> = path.split('/').skip(1).collect();
+    for (i, &segment) in segments.iter().enumerate() {
+        let is_last = i + 1 == segments.len();
+        match segment {
+            "." => {
+                if is_last {
+                    output.push("");
+                }
+            },
+            ".." => {
+                output.pop();
+                if is_last {
+                    output.push("");
+                }
+            },
+            segment => output.push(segment),
+        }
+    }
+    format!("/{}", output.join("/"))
+}
@@ -4,25 +4,51 @@
 //! It's aware of many tricky details such as the `Vary` header, proxy revalidation, and authenticated responses.
 
 use http::HeaderMap;
+use http::HeaderName;
 use http::HeaderValue;
 use http::Method;
 use http::Request;
 use http::Response;
 use http::StatusCode;
 use http::Uri;
-use std::collections::hash_map::Entry;
-use std::collections::HashMap;
+use range::RangeSpec;
+use range::StrongValidator;
+use std::ops::RangeInclusive;
+use std::sync::Arc;
 use std::time::Duration;
 use std::time::SystemTime;
-use time::format_description::well_known::Rfc2822;
-use time::OffsetDateTime;
+
+mod cache_control;
+mod explain;
+mod heuristic;
+mod http_date;
+mod invalidation;
+mod policy_override;
+mod range;
+mod rules;
+#[cfg(feature = "serde")]
+mod secs;
+mod variants;
+mod vary;
+pub use cache_control::{CacheControl, CacheControlParseError};
+pub use explain::{CacheabilityReport, CacheabilityRule, FreshnessSource, RuleOutcome};
+pub use heuristic::HeuristicFreshness;
+pub use http_date::{HttpDate, HttpDateParseError};
+pub use invalidation::invalidation_targets;
+pub use policy_override::{Override, OverrideContext, PolicyOverride};
+pub use range::ContentRange;
+pub use rules::{Rule, RuleMatch};
+pub use variants::VariantSet;
+pub use vary::{AcceptEncodingNormalizer, AcceptLanguageNormalizer, AcceptNormalizer, DeviceClassNormalizer, SecondaryKey, VaryNormalization, VaryNormalizer};
 
 // rfc7231 6.1
 const STATUS_CODE_CACHEABLE_BY_DEFAULT: &[u16] =
     &[200, 203, 204, 206, 300, 301, 308, 404, 405, 410, 414, 501];
 
-// This implementation does not understand partial responses (206)
-const UNDERSTOOD_STATUSES: &[u16] = &[
+/// Default for `CacheOptions::understood_statuses`.
+///
+/// Partial responses (206) are understood only with `CacheOptions::partial_content`.
+pub const UNDERSTOOD_STATUSES: &[u16] = &[
     200, 203, 204, 300, 301, 302, 303, 307, 308, 404, 405, 410, 414, 501,
 ];
 
@@ -46,70 +72,48 @@ const EXCLUDED_FROM_REVALIDATION_UPDATE: &[&str] = &[
     "content-range",
 ];
 
-type CacheControl = HashMap<Box<str>, Option<Box<str>>>;
+/// Fields kept in a 304 response generated by the cache
+const NOT_MODIFIED_HEADERS: &[&str] = &[
+    "age",
+    "cache-control",
+    "content-location",
+    "date",
+    "etag",
+    "expires",
+    "vary",
+];
 
+/// Lossy parsing of headers received from the network
 fn parse_cache_control<'a>(headers: impl IntoIterator<Item = &'a HeaderValue>) -> CacheControl {
-    let mut cc = CacheControl::new();
-    let mut is_valid = true;
-
-    for h in headers.into_iter().filter_map(|v| v.to_str().ok()) {
-        for part in h.split(',') {
-            // TODO: lame parsing
-            if part.trim().is_empty() {
-                continue;
-            }
-            let mut kv = part.splitn(2, '=');
-            let k = kv.next().unwrap().trim();
-            if k.is_empty() {
-                continue;
-            }
-            let v = kv.next().map(str::trim);
-            match cc.entry(k.into()) {
-                Entry::Occupied(e) => {
-                    // When there is more than one value present for a given directive (e.g., two Expires header fields, multiple Cache-Control: max-age directives),
-                    // the directive's value is considered invalid. Caches are encouraged to consider responses that have invalid freshness information to be stale
-                    if e.get().as_deref() != v {
-                        is_valid = false;
-                    }
-                }
-                Entry::Vacant(e) => {
-                    e.insert(v.map(|v| v.trim_matches('"')).map(From::from)); // TODO: bad unquoting
-                }
-            }
-        }
-    }
-    if !is_valid {
-        cc.insert("must-revalidate".into(), None);
+    let mut cc = CacheControl::parse_header_values_lossy(headers);
+    // When there is more than one value present for a given directive (e.g., two Expires header fields, multiple Cache-Control: max-age directives),
+    // the directive's value is considered invalid. Caches are encouraged to consider responses that have invalid freshness information to be stale
+    if cc.has_conflicting_duplicates() {
+        cc.insert("must-revalidate", None);
     }
     cc
 }
 
-fn format_cache_control(cc: &CacheControl) -> String {
-    let mut out = String::new();
-    for (k, v) in cc {
-        if !out.is_empty() {
-            out.push_str(", ");
-        }
-        out.push_str(k);
-        if let Some(v) = v {
-            out.push('=');
-            let needs_quote =
-                v.is_empty() || v.as_bytes().iter().any(|b| !b.is_ascii_alphanumeric());
-            if needs_quote {
-                out.push('"');
-            }
-            out.push_str(v);
-            if needs_quote {
-                out.push('"');
-            }
-        }
+/// Keeps only the `Surrogate-Control` directives understood by the cache.
+///
+/// `max-age=freshness+stale` uses only the freshness part. Directives targeted at
+/// a device token (`max-age=60;token`) are skipped, and so is `no-store-remote`.
+fn parse_surrogate_control<'a>(headers: impl IntoIterator<Item = &'a HeaderValue>) -> CacheControl {
+    let sc = CacheControl::parse_header_values_lossy(headers);
+    let mut cc = CacheControl::new();
+    if sc.no_store() {
+        cc.insert("no-store", None);
     }
-    out
+    if let Some(max_age) = sc.get("max-age").and_then(|v| v.split('+').next()) {
+        cc.insert("max-age", Some(max_age));
+    }
+    cc
 }
 
 /// Configuration options which control behavior of the cache. Use with `CachePolicy::new_options()`.
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Clone)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
 pub struct CacheOptions {
     /// If `true` (default), then the response is evaluated from a
     /// perspective of a shared cache (i.e. `private` is not cacheable and
@@ -123,6 +127,9 @@ pub struct CacheOptions {
     /// hasn't been modified for 100 days, it'll be cached for 100×0.1 = 10
     /// days.
     pub cache_heuristic: f32,
+    /// Limits and exceptions for the heuristic freshness lifetime, e.g. by `Content-Type`.
+    /// The default only uses `cache_heuristic`.
+    pub heuristic_freshness: HeuristicFreshness,
     /// `immutable_min_time_to_live` is a duration to assume as the
     /// default time to cache responses with `Cache-Control: immutable`. Note
     /// that per RFC these can become stale, so `max-age` still overrides the
@@ -134,6 +141,138 @@ pub struct CacheOptions {
     /// found in bad StackOverflow answers and PHP's "session limiter"
     /// defaults.
     pub ignore_cargo_cult: bool,
+    /// Status codes whose caching requirements are understood by the cache.
+    /// Responses with other statuses are never stored. The default is [`UNDERSTOOD_STATUSES`].
+    ///
+    /// This is also used for the `must-understand` directive, which overrides
+    /// `no-store` when the status code is understood.
+    ///
+    /// 206 (Partial Content) isn't affected by this list. It's understood only with `partial_content`.
+    pub understood_statuses: Vec<u16>,
+    /// Names of targeted cache control fields ([RFC 9213](https://httpwg.org/specs/rfc9213.html)),
+    /// such as `cdn-cache-control`, in order of precedence. Empty by default.
+    ///
+    /// The first of these fields present in the response (with a valid, non-empty value)
+    /// is used instead of `Cache-Control` and `Expires` to determine storability and freshness.
+    pub targeted_fields: Vec<String>,
+    /// If `true`, the `Surrogate-Control` header (Edge Architecture) is meant for this cache.
+    /// The default is `false`, which is right for caches that aren't a reverse proxy of the origin.
+    ///
+    /// Its `no-store` and `max-age` directives are used instead of `Cache-Control` and `Expires`,
+    /// unless one of `targeted_fields` is present. The header is removed from responses
+    /// returned by the cache.
+    pub surrogate_control: bool,
+    /// How to handle the difference between the origin server's `Date` and the local clock.
+    /// The default is `ClockSkewPolicy::TrustOrigin`.
+    pub clock_skew: ClockSkewPolicy,
+    /// If `true`, 206 (Partial Content) responses with a single byte range can be stored,
+    /// and `Range` requests are answered from stored ranges ([RFC 9111 section 3.4](https://httpwg.org/specs/rfc9111.html#combining.byte.ranges)).
+    /// The default is `false`.
+    pub partial_content: bool,
+    /// Normalization of request headers listed in `Vary`, so that equivalent requests
+    /// can use the same stored response. Empty by default, which compares values exactly.
+    ///
+    /// Normalizers aren't serialized. After deserializing a `CachePolicy`, add them again with
+    /// `CachePolicy::set_vary_normalizers()`.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub vary_normalizers: Vec<Arc<dyn VaryNormalizer>>,
+    /// Exceptions to the caching rules, e.g. a TTL forced for some URLs. The first override that applies to a response is used.
+    /// Empty by default.
+    ///
+    /// The override applied to a response is stored in its `CachePolicy`, but overrides aren't serialized themselves.
+    /// After deserializing a `CachePolicy`, add them again with `CachePolicy::set_policy_overrides()` to apply them to revalidated responses.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub policy_overrides: Vec<Arc<dyn PolicyOverride>>,
+    /// Overrides configured declaratively, e.g. in a config file. They're used if none of `policy_overrides` applies.
+    /// Empty by default.
+    pub rules: Vec<Rule>,
+}
+
+/// How the cache handles an origin server whose clock disagrees with the local clock.
+/// See `CachePolicy::clock_skew()`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub enum ClockSkewPolicy {
+    /// Use `Date` as it is. `Expires` and `Last-Modified` are relative to `Date`,
+    /// and the difference between `Date` and the response time counts towards the age
+    /// (when the policy is created with `CachePolicy::new_timed()`).
+    #[default]
+    TrustOrigin,
+    /// If `Date` is off by more than this, it's moved to be within this distance from the response time.
+    ///
+    /// With `Duration::ZERO` the origin's clock is ignored, and `Expires` is compared with the local clock.
+    Clamp(Duration),
+    /// `Expires` and `Last-Modified` are relative to `Date`, but the age doesn't depend on `Date`,
+    /// so the freshness lifetime always starts when the response has been received.
+    ///
+    /// Only policies created with `CachePolicy::new_timed()` count `Date` towards the age, so with
+    /// the other constructors this behaves the same as `TrustOrigin`.
+    ExpiresRelative,
+}
+
+/// Difference between the origin server's `Date` and the time the response has been received.
+/// Returned by `CachePolicy::clock_skew()`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ClockSkew {
+    /// `Date` is later than the response time, so the origin's clock is ahead
+    Ahead(Duration),
+    /// `Date` is earlier than the response time. This includes the time the response spent in transit.
+    Behind(Duration),
+}
+
+impl ClockSkew {
+    /// How far off the origin's clock is, regardless of the direction
+    #[must_use]
+    pub fn duration(&self) -> Duration {
+        match *self {
+            Self::Ahead(d) | Self::Behind(d) => d,
+        }
+    }
+}
+
+impl CacheOptions {
+    /// Key of stored responses that can be used for the request, if they have these names in their `Vary` header.
+    /// This is the same as their `CachePolicy::secondary_key()`.
+    ///
+    /// The `vary_names` can be values of `Vary` headers, i.e. comma-separated lists. Their order and case don't matter.
+    /// It's `None` if they include `*`.
+    ///
+    /// ```rust
+    /// use http_cache_semantics::{CacheOptions, CachePolicy};
+    /// let req = http::Request::get("/").header("accept-language", "en").body(()).unwrap();
+    /// let res = http::Response::builder().header("cache-control", "max-age=60").header("vary", "accept-language").body(()).unwrap();
+    /// let policy = CachePolicy::new(&req, &res);
+    ///
+    /// let options = CacheOptions::default();
+    /// assert_eq!(policy.secondary_key(), options.secondary_key_for_request(&req, ["Accept-Language"]));
+    /// ```
+    pub fn secondary_key_for_request<'a, Req: RequestLike>(&self, req: &Req, vary_names: impl IntoIterator<Item = &'a str>) -> Option<SecondaryKey> {
+        let names = vary::parse_vary_names(vary_names)?;
+        Some(self.secondary_key(&req.uri(), req.headers(), names))
+    }
+
+    fn secondary_key(&self, uri: &Uri, headers: &HeaderMap, mut names: Vec<String>) -> SecondaryKey {
+        names.sort_unstable();
+        let values: Vec<_> = names.iter().map(|name| {
+            let value = vary::combined_value(headers, name);
+            match self.vary_normalizer(name, uri, headers) {
+                Some(n) => n.normalize(value.as_deref().and_then(|v| std::str::from_utf8(v).ok())).map(String::into_bytes),
+                None => value,
+            }
+        }).collect();
+        SecondaryKey::new(names.iter().map(String::as_str).zip(values.iter().map(Option::as_deref)))
+    }
+
+    /// Normalization set by `rules` for the URI takes precedence.
+    /// It depends only on the request, so that a key computed for a new request matches the stored one.
+    fn vary_normalizer(&self, name: &str, uri: &Uri, headers: &HeaderMap) -> Option<&dyn VaryNormalizer> {
+        self.rules.iter()
+            .filter(|r| r.conditions.matches_uri(uri, headers))
+            .find_map(|r| r.vary_normalization.iter().find(|n| n.header_name().eq_ignore_ascii_case(name)))
+            .map(|n| n as &dyn VaryNormalizer)
+            .or_else(|| self.vary_normalizers.iter().find(|n| n.header_name().eq_ignore_ascii_case(name)).map(|n| &**n))
+    }
 }
 
 impl Default for CacheOptions {
@@ -141,8 +280,17 @@ impl Default for CacheOptions {
         Self {
             shared: true,
             cache_heuristic: 0.1, // 10% matches IE
+            heuristic_freshness: HeuristicFreshness::default(),
             immutable_min_time_to_live: Duration::from_secs(24 * 3600),
             ignore_cargo_cult: false,
+            understood_statuses: UNDERSTOOD_STATUSES.to_vec(),
+            targeted_fields: Vec::new(),
+            surrogate_control: false,
+            clock_skew: ClockSkewPolicy::TrustOrigin,
+            partial_content: false,
+            vary_normalizers: Vec::new(),
+            policy_overrides: Vec::new(),
+            rules: Vec::new(),
         }
     }
 }
@@ -151,6 +299,10 @@ impl Default for CacheOptions {
 /// HTTP RFC 7234 rules for user agents and shared caches. It's aware of many
 /// tricky details such as the Vary header, proxy revalidation, and
 /// authenticated responses.
+///
+/// With the `serde` feature it can be stored along with the response. `CacheOptions::vary_normalizers`
+/// and `CacheOptions::policy_overrides` aren't serialized, so after deserializing add them again with
+/// `set_vary_normalizers()` and `set_policy_overrides()`.
 #[derive(Debug, Clone)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct CachePolicy {
@@ -168,6 +320,21 @@ pub struct CachePolicy {
     res_cc: CacheControl,
     req_cc: CacheControl,
     response_time: SystemTime,
+    /// When the request has been sent, if known. Enables `corrected_initial_age` calculation.
+    #[cfg_attr(feature = "serde", serde(default))]
+    request_time: Option<SystemTime>,
+    /// Byte ranges of a stored 206 response, sorted. `None` if the complete response is stored.
+    #[cfg_attr(feature = "serde", serde(default))]
+    ranges: Option<Vec<RangeInclusive<u64>>>,
+    /// Name of the `CacheOptions::targeted_fields` header used instead of `Cache-Control`
+    #[cfg_attr(feature = "serde", serde(default))]
+    targeted_field: Option<Box<str>>,
+    /// A HEAD response didn't match the stored response, so it's stale until revalidated
+    #[cfg_attr(feature = "serde", serde(default))]
+    head_mismatch: bool,
+    /// Returned by the first of `CacheOptions::policy_overrides` that applies
+    #[cfg_attr(feature = "serde", serde(default))]
+    overridden: Option<Override>,
 }
 
 impl CachePolicy {
@@ -186,6 +353,7 @@ impl CachePolicy {
             status,
             req,
             res,
+            None,
             SystemTime::now(),
             Default::default(),
         )
@@ -206,15 +374,38 @@ impl CachePolicy {
         let method = req.method().clone();
         let res = res.headers().clone();
         let req = req.headers().clone();
-        Self::from_details(uri, method, status, req, res, response_time, opts)
+        Self::from_details(uri, method, status, req, res, None, response_time, opts)
+    }
+
+    /// Like `new_options()`, but also takes the time when the request has been sent.
+    ///
+    /// The delay between `request_time` and `response_time` counts towards the age of the response,
+    /// and the response is considered at least as old as the difference between its `Date` and `response_time`
+    /// (`corrected_initial_age` from [RFC 9111 section 4.2.3](https://httpwg.org/specs/rfc9111.html#age.calculations)).
+    #[inline]
+    pub fn new_timed<Req: RequestLike, Res: ResponseLike>(
+        req: &Req,
+        res: &Res,
+        request_time: SystemTime,
+        response_time: SystemTime,
+        opts: CacheOptions,
+    ) -> Self {
+        let uri = req.uri();
+        let status = res.status();
+        let method = req.method().clone();
+        let res = res.headers().clone();
+        let req = req.headers().clone();
+        Self::from_details(uri, method, status, req, res, Some(request_time), response_time, opts)
     }
 
+    #[allow(clippy::too_many_arguments)]
     fn from_details(
         uri: Uri,
         method: Method,
         status: StatusCode,
         req: HeaderMap,
         mut res: HeaderMap,
+        request_time: Option<SystemTime>,
         response_time: SystemTime,
         opts: CacheOptions,
     ) -> Self {
@@ -223,10 +414,7 @@ impl CachePolicy {
 
         // Assume that if someone uses legacy, non-standard uncecessary options they don't understand caching,
         // so there's no point stricly adhering to the blindly copy&pasted directives.
-        if opts.ignore_cargo_cult
-            && res_cc.get("pre-check").is_some()
-            && res_cc.get("post-check").is_some()
-        {
+        if opts.ignore_cargo_cult && res_cc.contains("pre-check") && res_cc.contains("post-check") {
             res_cc.remove("pre-check");
             res_cc.remove("post-check");
             res_cc.remove("no-cache");
@@ -234,7 +422,7 @@ impl CachePolicy {
             res_cc.remove("must-revalidate");
             res.insert(
                 "cache-control",
-                HeaderValue::from_str(&format_cache_control(&res_cc)).unwrap(),
+                HeaderValue::from_str(&res_cc.to_string()).unwrap(),
             );
             res.remove("expires");
             res.remove("pragma");
@@ -245,52 +433,307 @@ impl CachePolicy {
         if !res.contains_key("cache-control")
             && res
                 .get_str("pragma")
-                .map_or(false, |p| p.contains("no-cache"))
+                .is_some_and(|p| p.contains("no-cache"))
         {
-            res_cc.insert("no-cache".into(), None);
+            res_cc.insert("no-cache", None);
         }
 
-        Self { req, res, uri, status, method, opts, res_cc, req_cc, response_time }
+        // The first targeted field with a valid, non-empty value replaces Cache-Control and Expires (RFC 9213)
+        let mut targeted_field = None;
+        for name in &opts.targeted_fields {
+            match CacheControl::parse_targeted_header_values(res.get_all(name.as_str())) {
+                Ok(targeted_cc) if !targeted_cc.is_empty() => {
+                    res_cc = targeted_cc;
+                    targeted_field = Some(name.to_ascii_lowercase().into());
+                    break;
+                },
+                _ => {},
+            }
+        }
+        if opts.surrogate_control && targeted_field.is_none() {
+            let surrogate_cc = parse_surrogate_control(res.get_all("surrogate-control"));
+            if !surrogate_cc.is_empty() {
+                res_cc = surrogate_cc;
+                targeted_field = Some("surrogate-control".into());
+            }
+        }
+
+        let ctx = OverrideContext {
+            method: &method,
+            uri: &uri,
+            status,
+            request_headers: &req,
+            response_headers: &res,
+        };
+        let overridden = opts.policy_overrides.iter().map(|o| &**o)
+            .chain(opts.rules.iter().map(|r| r as &dyn PolicyOverride))
+            .find_map(|o| o.evaluate(&ctx));
+        if let Some(o) = &overridden {
+            for name in &o.strip_headers {
+                res.remove(name.to_ascii_lowercase().as_str());
+            }
+        }
+
+        // a shared cache MUST NOT store the specified field names, but can store the remainder of the response message.
+        if opts.shared {
+            if let Some(fields) = res_cc.private_fields() {
+                for name in fields {
+                    res.remove(name.to_ascii_lowercase().as_str());
+                }
+            }
+        }
+
+        let ranges = if opts.partial_content && status == StatusCode::PARTIAL_CONTENT {
+            res.get_str("content-range").and_then(ContentRange::parse).map(|cr| vec![cr.range])
+        } else {
+            None
+        };
+
+        Self { req, res, uri, status, method, opts, res_cc, req_cc, response_time, request_time, ranges, targeted_field, head_mismatch: false, overridden }
     }
 
     /// Returns `true` if the response can be stored in a cache. If it's
     /// `false` then you MUST NOT store either the request or the response.
+    ///
+    /// See `explain()` for the reason.
     pub fn is_storable(&self) -> bool {
-        // The "no-store" request directive indicates that a cache MUST NOT store any part of either this request or any response to it.
-        !self.req_cc.contains_key("no-store") &&
+        self.storability_rules().iter().all(|r| !r.applies)
+    }
+
+    fn storability_rules(&self) -> [RuleOutcome; 8] {
+        let shared = self.opts.shared;
+        // A `PolicyOverride` can lift the rules that come from the response's directives,
+        // but not the ones that protect the request or require understanding the response
+        let forced = self.overridden.as_ref().and_then(|o| o.storable) == Some(true);
+        [
+            RuleOutcome {
+                rule: CacheabilityRule::Override(self.overridden.as_ref().map(|o| o.name.clone()).unwrap_or_default()),
+                applies: self.overridden.as_ref().and_then(|o| o.storable) == Some(false),
+            },
+            // The "no-store" request directive indicates that a cache MUST NOT store any part of either this request or any response to it.
+            RuleOutcome {
+                rule: CacheabilityRule::RequestNoStore,
+                applies: self.req_cc.contains("no-store"),
+            },
             // A cache MUST NOT store a response to any request, unless:
             // The request method is understood by the cache and defined as being cacheable, and
-            (Method::GET == self.method ||
-                Method::HEAD == self.method ||
-                (Method::POST == self.method && self.has_explicit_expiration())) &&
+            RuleOutcome {
+                rule: CacheabilityRule::MethodNotCacheable(self.method.clone()),
+                applies: !(Method::GET == self.method ||
+                    Method::HEAD == self.method ||
+                    (Method::POST == self.method && self.has_explicit_expiration())),
+            },
             // the response status code is understood by the cache, and
-            UNDERSTOOD_STATUSES.contains(&self.status.as_u16()) &&
+            RuleOutcome {
+                rule: CacheabilityRule::StatusNotUnderstood(self.status),
+                applies: !self.understands_status(),
+            },
             // the "no-store" cache directive does not appear in request or response header fields, and
-            !self.res_cc.contains_key("no-store") &&
+            RuleOutcome {
+                rule: CacheabilityRule::ResponseNoStore,
+                // the cache SHOULD ignore the no-store directive if it understands the status code's caching requirements (must-understand)
+                applies: !forced && self.res_cc.no_store() && !(self.res_cc.must_understand() && self.understands_status()),
+            },
             // the "private" response directive does not appear in the response, if the cache is shared, and
-            (!self.opts.shared || !self.res_cc.contains_key("private")) &&
+            // (qualified private="field" only forbids storing the listed fields)
+            RuleOutcome {
+                rule: CacheabilityRule::PrivateInSharedCache,
+                applies: !forced && shared && self.res_cc.private() && self.res_cc.private_fields().is_none(),
+            },
             // the Authorization header field does not appear in the request, if the cache is shared,
-            (!self.opts.shared ||
-                !self.req.contains_key("authorization") ||
-                self.allows_storing_authenticated()) &&
+            RuleOutcome {
+                rule: CacheabilityRule::AuthorizationInSharedCache,
+                applies: shared && self.req.contains_key("authorization") && !self.allows_storing_authenticated(),
+            },
             // the response either:
-            // contains an Expires header field, or
-            (self.res.contains_key("expires") ||
-                // contains a max-age response directive, or
-                // contains a s-maxage response directive and the cache is shared, or
-                // contains a public response directive.
-                self.res_cc.contains_key("max-age") ||
-                (self.opts.shared && self.res_cc.contains_key("s-maxage")) ||
-                self.res_cc.contains_key("public") ||
-                // has a status code that is defined as cacheable by default
-                STATUS_CODE_CACHEABLE_BY_DEFAULT.contains(&self.status.as_u16()))
+            RuleOutcome {
+                rule: CacheabilityRule::NoFreshnessInformation,
+                applies: !(forced ||
+                    // contains an Expires header field, or
+                    self.expires().is_some() ||
+                    // contains a max-age response directive, or
+                    // contains a s-maxage response directive and the cache is shared, or
+                    // contains a public response directive.
+                    self.res_cc.contains("max-age") ||
+                    (shared && self.res_cc.contains("s-maxage")) ||
+                    self.res_cc.contains("public") ||
+                    // has a status code that is defined as cacheable by default
+                    STATUS_CODE_CACHEABLE_BY_DEFAULT.contains(&self.status.as_u16())),
+            },
+        ]
+    }
+
+    /// Lists every rule that `is_storable()` and `time_to_live()` have evaluated,
+    /// and where the freshness lifetime comes from.
+    ///
+    /// Use it to debug why a response isn't cached.
+    #[must_use]
+    pub fn explain(&self) -> CacheabilityReport {
+        let (lifetime, mut freshness_source) = self.freshness_lifetime();
+        if self.overridden.as_ref().is_some_and(|o| o.lifetime(lifetime) != lifetime || o.ttl.is_some()) {
+            freshness_source = FreshnessSource::Override;
+        }
+        CacheabilityReport {
+            storable: self.is_storable(),
+            storability_rules: self.storability_rules().into(),
+            reuse_rules: self.reuse_rules().into(),
+            freshness_lifetime: self.max_age(),
+            freshness_source,
+            applied_override: self.overridden.clone(),
+        }
+    }
+
+    /// `Expires` header, unless a targeted field overrides it
+    fn expires(&self) -> Option<&str> {
+        if self.targeted_field.is_some() {
+            return None;
+        }
+        self.res.get_str("expires")
+    }
+
+    fn understands_status(&self) -> bool {
+        // a 206 is understood only with range tracking, and a single, valid Content-Range
+        if self.status == StatusCode::PARTIAL_CONTENT {
+            return self.ranges.is_some();
+        }
+        self.opts.understood_statuses.contains(&self.status.as_u16())
+    }
+
+    /// Byte ranges of the representation that are stored, if the stored response is partial (206).
+    ///
+    /// `None` if the complete response is stored. Ranges are sorted and don't overlap.
+    /// This requires `CacheOptions::partial_content`.
+    pub fn stored_ranges(&self) -> Option<&[RangeInclusive<u64>]> {
+        self.ranges.as_deref()
+    }
+
+    /// Length of the complete representation, from `Content-Range` or `Content-Length`
+    fn complete_length(&self) -> Option<u64> {
+        if self.status == StatusCode::PARTIAL_CONTENT {
+            ContentRange::parse(self.res.get_str("content-range")?)?.complete_length
+        } else {
+            self.res.get_str("content-length")?.trim().parse().ok()
+        }
+    }
+
+    /// Which part of the stored response can be used for the request.
+    /// `Ok(None)` for the complete response, or an error if the request needs bytes that aren't stored.
+    fn served_range(&self, req: &impl RequestLike) -> Result<Option<RangeInclusive<u64>>, StaleReason> {
+        if !self.opts.partial_content && self.ranges.is_none() {
+            return Ok(None);
+        }
+        let spec = self.requested_range(req);
+        let complete_length = self.complete_length();
+        match (&self.ranges, spec) {
+            (None, spec) => Ok(spec.and_then(|spec| spec.resolve(complete_length))),
+            (Some(ranges), Some(spec)) => spec.resolve(complete_length)
+                .filter(|wanted| ranges.iter().any(|r| r.contains(wanted.start()) && r.contains(wanted.end())))
+                .map(Some)
+                .ok_or(StaleReason::RangeNotStored),
+            (Some(_), None) => Err(StaleReason::RangeNotStored),
+        }
+    }
+
+    /// The `Range` of the request, unless it has to be ignored
+    fn requested_range(&self, req: &impl RequestLike) -> Option<RangeSpec> {
+        let headers = req.headers();
+        RangeSpec::from_request(headers).filter(|_| {
+            // A server MUST ignore a Range header field received with a request method that is unrecognized or for which range handling is not defined
+            *req.method() == Method::GET
+                // If the validator given in the If-Range header field matches the current validator for the selected representation of the target resource,
+                // then the server SHOULD process the Range header field as requested. If the validator does not match, the server MUST ignore the Range header field.
+                && headers.get_str("if-range").is_none_or(|if_range| {
+                    StrongValidator::of_response(&self.res).is_some_and(|v| v.matches_if_range(if_range))
+                })
+        })
+    }
+
+    /// Records that only the first `received_len` bytes of the response body have been stored,
+    /// e.g. because the connection has been interrupted ([RFC 9111 section 3.3](https://httpwg.org/specs/rfc9111.html#incomplete.responses)).
+    ///
+    /// The incomplete response is used only for `Range` requests within the stored bytes.
+    /// For other requests `before_request()` returns `Stale` with `StaleReason::RangeNotStored`,
+    /// and if the response has a strong validator, a `Range` request for the missing bytes with `If-Range`.
+    /// The server's 206 response can then be added to the stored body (see `AfterResponse::Combined`).
+    ///
+    /// For a partial (206) response, `received_len` is the length of the stored body, i.e. of all `stored_ranges()` together.
+    pub fn mark_incomplete(&mut self, received_len: u64) {
+        let stored = match self.ranges.take() {
+            Some(ranges) => ranges,
+            None => match self.complete_length() {
+                Some(len) if received_len >= len => return,
+                _ => vec![0..=u64::MAX],
+            },
+        };
+        let mut remaining = received_len;
+        let mut ranges = Vec::with_capacity(stored.len());
+        for r in stored {
+            if remaining == 0 {
+                break;
+            }
+            let len = (r.end() - r.start()).saturating_add(1);
+            if remaining < len {
+                ranges.push(*r.start()..=r.start() + remaining - 1);
+                break;
+            }
+            remaining -= len;
+            ranges.push(r);
+        }
+        self.ranges = Some(ranges);
+    }
+
+    /// Request for the bytes missing from the stored response (RFC 9111 section 3.3)
+    fn completion_request<Req: RequestLike>(&self, req: &Req) -> http::request::Parts {
+        let mut headers = Self::copy_without_hop_by_hop_headers(req.headers());
+        // the stored validators can't be used for revalidation, since a 304 wouldn't give the missing bytes,
+        // but If-Range with a strong validator makes the server send either the missing bytes, or the complete new response.
+        if let Some(((first, last), validator)) = self.missing_range(req).zip(StrongValidator::of_response(&self.res)) {
+            let range = match last {
+                Some(last) => format!("bytes={first}-{last}"),
+                None => format!("bytes={first}-"),
+            };
+            headers.insert("range", HeaderValue::from_str(&range).unwrap());
+            headers.insert("if-range", validator.to_header_value());
+        }
+        self.request_from_headers(headers)
+    }
+
+    /// The first missing byte of the requested range (and its last byte, if known),
+    /// if the stored bytes cover the beginning of the requested range
+    fn missing_range(&self, req: &impl RequestLike) -> Option<(u64, Option<u64>)> {
+        if self.method != Method::GET || *req.method() != Method::GET {
+            return None;
+        }
+        let complete_length = self.complete_length();
+        let (first, last) = match self.requested_range(req) {
+            Some(spec) => {
+                let wanted = spec.resolve(complete_length)?;
+                (*wanted.start(), Some(*wanted.end()))
+            },
+            None => (0, complete_length.and_then(|len| len.checked_sub(1))),
+        };
+        let stored = self.ranges.as_ref()?.iter().find(|r| r.contains(&first))?;
+        Some((stored.end().checked_add(1)?, last))
+    }
+
+    /// `cached_response()` with `Content-Range` for the requested part
+    fn cached_range_response(&self, range: Option<RangeInclusive<u64>>, now: SystemTime) -> http::response::Parts {
+        let mut parts = self.cached_response(now);
+        if let Some(range) = range {
+            parts.status = StatusCode::PARTIAL_CONTENT;
+            let len = range.end() - range.start() + 1;
+            let content_range = ContentRange { range, complete_length: self.complete_length() };
+            parts.headers.insert("content-range", HeaderValue::from_str(&content_range.to_string()).unwrap());
+            parts.headers.insert("content-length", HeaderValue::from(len));
+        }
+        parts
     }
 
     fn has_explicit_expiration(&self) -> bool {
         // 4.2.1 Calculating Freshness Lifetime
-        (self.opts.shared && self.res_cc.contains_key("s-maxage"))
-            || self.res_cc.contains_key("max-age")
-            || self.res.contains_key("expires")
+        (self.opts.shared && self.res_cc.contains("s-maxage"))
+            || self.res_cc.contains("max-age")
+            || self.expires().is_some()
     }
 
     /// Returns whether the cached response is still fresh in the context of
@@ -300,6 +743,10 @@ impl CachePolicy {
     /// response this cache policy has been created with, and the response can
     /// be reused without contacting the server.
     ///
+    /// If it returns `StaleWhileRevalidate`, then the response is stale, but
+    /// within its `stale-while-revalidate` window, so the cached response can be
+    /// used immediately while the revalidation request is sent in the background.
+    ///
     /// If it returns `Stale`, then the response may not be matching at all
     /// (e.g. it's for a different URL or method), or may require to be
     /// refreshed first. Either way, the new request's headers will have been
@@ -307,111 +754,265 @@ impl CachePolicy {
     pub fn before_request<Req: RequestLike>(&self, req: &Req, now: SystemTime) -> BeforeRequest {
         let req_headers = req.headers();
 
-        // revalidation allowed via HEAD
-        let (matches, may_revalidate) = self.request_matches(req);
+        let mut served_range = None;
+        let reason = match self.request_mismatch(req) {
+            None => match self.served_range(req) {
+                Ok(range) => match self.reuse_denial(req_headers, now) {
+                    None if self.client_not_modified(req) => return BeforeRequest::Fresh(self.not_modified_response(now)),
+                    None => return BeforeRequest::Fresh(self.cached_range_response(range, now)),
+                    Some(reason) => {
+                        served_range = range;
+                        reason
+                    },
+                },
+                Err(reason) => reason,
+            },
+            Some(reason) => reason,
+        };
+        let matches = !reason.is_mismatch();
+
+        if matches && reason != StaleReason::RangeNotStored && self.allows_stale_while_revalidate(req_headers, now) {
+            return BeforeRequest::StaleWhileRevalidate {
+                response: self.cached_range_response(served_range, now),
+                revalidation_request: self.revalidation_request(req),
+            };
+        }
 
-        if matches && self.satisfies_without_revalidation(req_headers, now) {
-            BeforeRequest::Fresh(self.cached_response(now))
-        } else if may_revalidate {
+        // A cache that receives only-if-cached SHOULD either respond using a stored response
+        // consistent with the other constraints of the request or respond with a 504 (Gateway Timeout)
+        if parse_cache_control(req_headers.get_all("cache-control")).only_if_cached() {
+            return BeforeRequest::GatewayTimeout(self.gateway_timeout_response(now));
+        }
+
+        if reason == StaleReason::RangeNotStored {
+            BeforeRequest::Stale {
+                request: self.completion_request(req),
+                matches,
+                reason,
+            }
+        // revalidation allowed via HEAD
+        } else if matches || (reason == StaleReason::MethodMismatch && Method::HEAD == req.method()) {
             BeforeRequest::Stale {
                 request: self.revalidation_request(req),
                 matches,
+                reason,
             }
         } else {
             BeforeRequest::Stale {
                 request: self.request_from_headers(req_headers.clone()),
                 matches,
+                reason,
             }
         }
     }
 
-    fn satisfies_without_revalidation(&self, req_headers: &HeaderMap, now: SystemTime) -> bool {
-        // When presented with a request, a cache MUST NOT reuse a stored response, unless:
-        // the presented request does not contain the no-cache pragma (Section 5.4), nor the no-cache cache directive,
-        // unless the stored response is successfully validated (Section 4.3), and
-        let req_cc = parse_cache_control(req_headers.get_all("cache-control"));
-        if req_cc.contains_key("no-cache")
-            || req_headers
-                .get_str("pragma")
-                .map_or(false, |v| v.contains("no-cache"))
-        {
+    /// Evaluates the client's `If-None-Match` or `If-Modified-Since` against the stored response ([RFC 9111 section 4.3.2](https://httpwg.org/specs/rfc9111.html#conditional.requests.with.cached.responses))
+    fn client_not_modified<Req: RequestLike>(&self, req: &Req) -> bool {
+        if (req.method() != Method::GET && req.method() != Method::HEAD) || !self.status.is_success() {
             return false;
         }
+        let headers = req.headers();
+        // the cache can't answer these with 304, and doesn't evaluate them
+        if headers.contains_key("if-match") || headers.contains_key("if-unmodified-since") {
+            return false;
+        }
+        if headers.contains_key("if-none-match") {
+            // uses the weak comparison function
+            let etag = self.res.get_str("etag").map(|e| e.trim().trim_start_matches("W/"));
+            return get_all_comma(headers.get_all("if-none-match"))
+                .any(|tag| tag == "*" || Some(tag.trim_start_matches("W/")) == etag);
+        }
+        let Some(since) = headers.get_str("if-modified-since").and_then(|d| self.parse_date(d)) else {
+            return false;
+        };
+        // If the Last-Modified header field is not present in a selected stored response, a cache SHOULD use
+        // the stored response's Date field value (or, if no Date field is present, the time that the stored response was received)
+        let last_modified = self.date_header("last-modified").unwrap_or_else(|| self.raw_server_date());
+        HttpDate::from(last_modified) <= HttpDate::from(since)
+    }
 
-        if let Some(max_age) = req_cc
-            .get("max-age")
-            .and_then(|v| v.as_ref())
-            .and_then(|p| p.parse().ok())
-        {
-            if self.age(now) > Duration::from_secs(max_age) {
-                return false;
+    /// `304 Not Modified` with the header fields that a server would send in it ([RFC 9110 section 15.4.5](https://httpwg.org/specs/rfc9110.html#status.304))
+    fn not_modified_response(&self, now: SystemTime) -> http::response::Parts {
+        let mut parts = self.cached_response(now);
+        parts.status = StatusCode::NOT_MODIFIED;
+        let has_etag = parts.headers.contains_key("etag");
+        let old_headers = std::mem::take(&mut parts.headers);
+        let mut name = None;
+        for (header, value) in old_headers {
+            name = header.or(name);
+            let Some(name) = &name else { continue };
+            // Last-Modified might be useful if the response does not have an ETag field
+            let keep = NOT_MODIFIED_HEADERS.contains(&name.as_str())
+                || (name == "last-modified" && !has_etag)
+                || self.opts.targeted_fields.iter().any(|f| f.eq_ignore_ascii_case(name.as_str()));
+            if keep {
+                parts.headers.append(name.clone(), value);
             }
         }
+        parts
+    }
 
-        if let Some(min_fresh) = req_cc
-            .get("min-fresh")
-            .and_then(|v| v.as_ref())
-            .and_then(|p| p.parse().ok())
-        {
-            if self.time_to_live(now) < Duration::from_secs(min_fresh) {
-                return false;
-            }
+    /// Response to `only-if-cached` requests that can't be satisfied from the cache
+    fn gateway_timeout_response(&self, now: SystemTime) -> http::response::Parts {
+        Response::builder()
+            .status(StatusCode::GATEWAY_TIMEOUT)
+            .header("date", HeaderValue::from_str(&HttpDate::from(now).to_string()).unwrap())
+            .body(())
+            .unwrap()
+            .into_parts().0
+    }
+
+    /// Why the stored response can't be used for the matching request, or `None` if it can be
+    fn reuse_denial(&self, req_headers: &HeaderMap, now: SystemTime) -> Option<StaleReason> {
+        let req_cc = parse_cache_control(req_headers.get_all("cache-control"));
+        if let Some(reason) = self.request_denial(&req_cc, req_headers, now) {
+            return Some(reason);
         }
 
         // the stored response is either:
         // fresh, or allowed to be served stale
         if self.is_stale(now) {
             // If no value is assigned to max-stale, then the client is willing to accept a stale response of any age.
-            let max_stale = req_cc.get("max-stale");
-            let has_max_stale = max_stale.is_some();
-            let max_stale = max_stale
-                .and_then(|m| m.as_ref())
-                .and_then(|s| s.parse().ok());
-            let allows_stale = !self.res_cc.contains_key("must-revalidate")
-                && has_max_stale
-                && max_stale.map_or(true, |val| {
-                    Duration::from_secs(val) > self.age(now) - self.max_age()
+            let allows_stale = !self.res_cc.must_revalidate()
+                && req_cc.max_stale().is_some_and(|max_stale| {
+                    max_stale.is_none_or(|val| val > self.age(now) - self.max_age())
                 });
             if !allows_stale {
-                return false;
+                let rules = self.storability_rules().into_iter().chain(self.reuse_rules());
+                return Some(match rules.into_iter().find(|r| r.applies) {
+                    Some(r) => StaleReason::NotReusable(r.rule),
+                    None => StaleReason::Expired,
+                });
             }
         }
 
-        true
+        None
     }
 
-    /// returns: matches including method, matches allowing head
-    fn request_matches<Req: RequestLike>(&self, req: &Req) -> (bool, bool) {
-        // The presented effective request URI and that of the stored response match, and
-        let matches = req.is_same_uri(&self.uri) &&
-            (self.req.get("host") == req.headers().get("host")) &&
-            // selecting header fields nominated by the stored response (if any) match those presented, and
-            self.vary_matches(req);
-        let exact_match = matches && self.method == req.method();
+    fn request_denial(&self, req_cc: &CacheControl, req_headers: &HeaderMap, now: SystemTime) -> Option<StaleReason> {
+        // When presented with a request, a cache MUST NOT reuse a stored response, unless:
+        // the presented request does not contain the no-cache pragma (Section 5.4), nor the no-cache cache directive,
+        // unless the stored response is successfully validated (Section 4.3), and
+        if req_cc.no_cache()
+            || req_headers
+                .get_str("pragma")
+                .is_some_and(|v| v.contains("no-cache"))
+        {
+            return Some(StaleReason::RequestNoCache);
+        }
+
+        if let Some(max_age) = req_cc.max_age() {
+            if self.age(now) > max_age {
+                return Some(StaleReason::RequestMaxAge);
+            }
+        }
+
+        if let Some(min_fresh) = req_cc.min_fresh() {
+            if self.time_to_live(now) < min_fresh {
+                return Some(StaleReason::RequestMinFresh);
+            }
+        }
+        None
+    }
+
+    /// RFC 5861: the stale response may be served while it's asynchronously revalidated
+    fn allows_stale_while_revalidate(&self, req_headers: &HeaderMap, now: SystemTime) -> bool {
+        let grace = self.overridden.as_ref().and_then(|o| o.grace);
+        let Some(window) = grace.or_else(|| self.res_cc.stale_while_revalidate()) else {
+            return false;
+        };
+        // Directives that require revalidation take precedence over serving stale
+        if self.forbids_reuse() || self.res_cc.must_revalidate() {
+            return false;
+        }
+        let req_cc = parse_cache_control(req_headers.get_all("cache-control"));
+        if self.request_denial(&req_cc, req_headers, now).is_some() {
+            return false;
+        }
+        let staleness = self.age(now).saturating_sub(self.max_age());
+        staleness < window
+    }
 
+    /// Why the request is for something else than the stored response, or `None` if it matches.
+    ///
+    /// The method is checked last, since revalidation is allowed via HEAD.
+    fn request_mismatch<Req: RequestLike>(&self, req: &Req) -> Option<StaleReason> {
+        // The presented effective request URI and that of the stored response match, and
+        if !req.is_same_uri(&self.uri) {
+            return Some(StaleReason::UriMismatch);
+        }
+        if self.req.get("host") != req.headers().get("host") {
+            return Some(StaleReason::HostMismatch);
+        }
+        // selecting header fields nominated by the stored response (if any) match those presented, and
+        if let Some(reason) = self.vary_mismatch(req) {
+            return Some(reason);
+        }
         // the request method associated with the stored response allows it to be used for the presented request, and
-        (exact_match, exact_match || Method::HEAD == req.method())
+        if self.method != req.method() {
+            return Some(StaleReason::MethodMismatch);
+        }
+        None
     }
 
     fn allows_storing_authenticated(&self) -> bool {
         //  following Cache-Control response directives (Section 5.2.2) have such an effect: must-revalidate, public, and s-maxage.
-        self.res_cc.contains_key("must-revalidate")
-            || self.res_cc.contains_key("public")
-            || self.res_cc.contains_key("s-maxage")
+        self.res_cc.contains("must-revalidate")
+            || self.res_cc.contains("public")
+            || self.res_cc.contains("s-maxage")
     }
 
-    fn vary_matches<Req: RequestLike>(&self, req: &Req) -> bool {
-        for name in get_all_comma(self.res.get_all("vary")) {
-            // A Vary header field-value of "*" always fails to match
-            if name == "*" {
-                return false;
-            }
-            let name = name.trim().to_ascii_lowercase();
-            if req.headers().get(&name) != self.req.get(&name) {
-                return false;
+    fn vary_mismatch<Req: RequestLike>(&self, req: &Req) -> Option<StaleReason> {
+        // A Vary header field-value of "*" always fails to match
+        let Some(names) = vary::vary_names(&self.res) else {
+            return Some(StaleReason::VaryStar);
+        };
+        for name in names {
+            // all field lines of the header are compared, not just the first one
+            let stored = vary::combined_value(&self.req, &name);
+            let presented = vary::combined_value(req.headers(), &name);
+            let matches = match self.opts.vary_normalizer(&name, &self.uri, &self.req) {
+                Some(n) => {
+                    let normalize = |v: Option<&[u8]>| n.normalize(v.and_then(|v| std::str::from_utf8(v).ok()));
+                    normalize(stored.as_deref()) == normalize(presented.as_deref())
+                },
+                None => presented == stored,
+            };
+            if !matches {
+                let to_value = |v: Option<Vec<u8>>| v.and_then(|v| HeaderValue::from_bytes(&v).ok());
+                return Some(StaleReason::VaryMismatch {
+                    // can't fail, since it's been parsed from a header value already
+                    name: HeaderName::from_bytes(name.as_bytes()).unwrap(),
+                    stored: to_value(stored),
+                    presented: to_value(presented),
+                });
             }
         }
-        true
+        None
+    }
+
+    /// Key of the variant this response is for, among stored responses for the same URI.
+    /// It's computed from the stored request's values of headers listed in `Vary`,
+    /// normalized with `CacheOptions::vary_normalizers` (or `Rule::vary_normalization`).
+    ///
+    /// Look it up with `CacheOptions::secondary_key_for_request()`. Responses without `Vary` all have the same key.
+    /// It's `None` for `Vary: *`, since such responses can't be reused.
+    #[must_use]
+    pub fn secondary_key(&self) -> Option<SecondaryKey> {
+        let names = vary::vary_names(&self.res)?;
+        Some(self.opts.secondary_key(&self.uri, &self.req, names))
+    }
+
+    /// Replaces `CacheOptions::vary_normalizers`, which aren't serialized with the policy.
+    pub fn set_vary_normalizers(&mut self, normalizers: Vec<Arc<dyn VaryNormalizer>>) {
+        self.opts.vary_normalizers = normalizers;
+    }
+
+    /// Replaces `CacheOptions::policy_overrides`, which aren't serialized with the policy.
+    ///
+    /// The override already applied to the stored response is kept until the response is revalidated.
+    pub fn set_policy_overrides(&mut self, overrides: Vec<Arc<dyn PolicyOverride>>) {
+        self.opts.policy_overrides = overrides;
     }
 
     fn copy_without_hop_by_hop_headers(in_headers: &HeaderMap) -> HeaderMap {
@@ -449,8 +1050,26 @@ impl CachePolicy {
     ///
     /// It returns response "parts" without a body. You can upgrade it to a full
     /// response with `Response::from_parts(parts, BYOB)`
+    ///
+    /// Fields listed in qualified `no-cache="…"` are removed, since they
+    /// can't be reused without revalidation.
     fn cached_response(&self, now: SystemTime) -> http::response::Parts {
+        let mut parts = self.validated_response(now);
+        if let Some(fields) = self.res_cc.no_cache_fields() {
+            for name in fields {
+                parts.headers.remove(name.to_ascii_lowercase().as_str());
+            }
+        }
+        parts
+    }
+
+    /// Like `cached_response()`, but for a response that has just been received or revalidated
+    fn validated_response(&self, now: SystemTime) -> http::response::Parts {
         let mut headers = Self::copy_without_hop_by_hop_headers(&self.res);
+        // Surrogate-Control is for the reverse proxy only, and must not be forwarded
+        if self.opts.surrogate_control {
+            headers.remove("surrogate-control");
+        }
         let age = self.age(now);
         let day = Duration::from_secs(3600 * 24);
 
@@ -462,15 +1081,18 @@ impl CachePolicy {
                 HeaderValue::from_static(r#"113 - "rfc7234 5.5.4""#),
             );
         }
-        let date = OffsetDateTime::from(now);
         headers.insert(
             "age",
             HeaderValue::from_str(&age.as_secs().to_string()).unwrap(),
         );
         headers.insert(
             "date",
-            HeaderValue::from_str(&date.format(&Rfc2822).unwrap()).unwrap(),
+            HeaderValue::from_str(&HttpDate::from(now).to_string()).unwrap(),
         );
+        // Tells where the unusual caching came from
+        if let Some(value) = self.overridden.as_ref().filter(|o| !o.name.is_empty()).and_then(|o| HeaderValue::from_str(&o.name).ok()) {
+            headers.insert("cache-override", value);
+        }
 
         let mut parts = Response::builder()
             .status(self.status)
@@ -482,28 +1104,75 @@ impl CachePolicy {
     }
 
     fn raw_server_date(&self) -> SystemTime {
-        let date = self
-            .res
-            .get_str("date")
-            .and_then(|d| OffsetDateTime::parse(d, &Rfc2822).ok())
-            .and_then(|d| {
-                SystemTime::UNIX_EPOCH.checked_add(Duration::from_secs(d.unix_timestamp() as u64))
-            });
-        date.unwrap_or(self.response_time)
+        self.date_header("date").unwrap_or(self.response_time)
+    }
+
+    /// Difference between the origin server's `Date` header and the local time when the response has been received.
+    ///
+    /// `None` if the response has no valid `Date`. Note that `Date` has a precision of one second,
+    /// and is generated before the response is sent, so small differences are normal.
+    /// See `CacheOptions::clock_skew` for ways of compensating for it.
+    pub fn clock_skew(&self) -> Option<ClockSkew> {
+        let date = self.date_header("date")?;
+        Some(match date.duration_since(self.response_time) {
+            Ok(ahead) => ClockSkew::Ahead(ahead),
+            Err(behind) => ClockSkew::Behind(behind.duration()),
+        })
+    }
+
+    /// `Date` adjusted according to `CacheOptions::clock_skew`. Freshness lifetime is relative to it.
+    fn server_date(&self) -> SystemTime {
+        let date = self.raw_server_date();
+        match self.opts.clock_skew {
+            ClockSkewPolicy::TrustOrigin | ClockSkewPolicy::ExpiresRelative => date,
+            ClockSkewPolicy::Clamp(max) => {
+                let earliest = self.response_time.checked_sub(max).unwrap_or(SystemTime::UNIX_EPOCH);
+                let date = date.max(earliest);
+                // a limit past the end of time doesn't limit anything
+                self.response_time.checked_add(max).map_or(date, |latest| date.min(latest))
+            },
+        }
+    }
+
+    /// Parses a date header of the stored response. Two-digit years are relative to the response time.
+    fn date_header(&self, name: &str) -> Option<SystemTime> {
+        self.res.get_str(name).and_then(|d| self.parse_date(d))
+    }
+
+    fn parse_date(&self, value: &str) -> Option<SystemTime> {
+        HttpDate::parse_relative_to(value, self.response_time).ok().map(SystemTime::from)
     }
 
     /// Tells how long the response has been sitting in cache(s).
     ///
     /// Value of the `Age` header, updated for the current time.
+    ///
+    /// If the policy has been created with `new_timed()`, it also accounts for the
+    /// response delay and the difference between `Date` and the response time.
     pub fn age(&self, now: SystemTime) -> Duration {
-        let mut age = self.age_header_value();
+        let mut age = self.corrected_initial_age();
 
         if let Ok(resident_time) = now.duration_since(self.response_time) {
-            age += resident_time;
+            age = age.saturating_add(resident_time);
         }
         age
     }
 
+    /// Age of the response when it has been received (RFC 9111 section 4.2.3)
+    fn corrected_initial_age(&self) -> Duration {
+        let age_value = self.age_header_value();
+        let Some(request_time) = self.request_time else {
+            return age_value;
+        };
+        let apparent_age = match self.opts.clock_skew {
+            ClockSkewPolicy::ExpiresRelative => Duration::ZERO,
+            _ => self.response_time.duration_since(self.server_date()).unwrap_or_default(),
+        };
+        let response_delay = self.response_time.duration_since(request_time).unwrap_or_default();
+        let corrected_age_value = age_value.saturating_add(response_delay);
+        apparent_age.max(corrected_age_value)
+    }
+
     fn age_header_value(&self) -> Duration {
         Duration::from_secs(
             self.res
@@ -513,77 +1182,116 @@ impl CachePolicy {
         )
     }
 
+    /// Rules that make the response always stale, regardless of its freshness lifetime
+    fn reuse_rules(&self) -> [RuleOutcome; 5] {
+        let shared = self.opts.shared;
+        [
+            // qualified no-cache="field" only requires revalidation of the listed fields
+            RuleOutcome {
+                rule: CacheabilityRule::NoCache,
+                applies: self.res_cc.no_cache() && self.res_cc.no_cache_fields().is_none(),
+            },
+            // Shared responses with cookies are cacheable according to the RFC, but IMHO it'd be unwise to do so by default
+            // so this implementation requires explicit opt-in via public header
+            RuleOutcome {
+                rule: CacheabilityRule::SetCookieInSharedCache,
+                applies: shared
+                    && self.res.contains_key("set-cookie")
+                    && !self.res_cc.contains("public")
+                    && !self.res_cc.contains("immutable"),
+            },
+            RuleOutcome {
+                rule: CacheabilityRule::VaryStar,
+                applies: vary::vary_names(&self.res).is_none(),
+            },
+            RuleOutcome {
+                rule: CacheabilityRule::ProxyRevalidate,
+                applies: shared && self.res_cc.contains("proxy-revalidate"),
+            },
+            RuleOutcome {
+                rule: CacheabilityRule::HeadResponseMismatch,
+                applies: self.head_mismatch,
+            },
+        ]
+    }
+
+    fn forbids_reuse(&self) -> bool {
+        !self.is_storable() || self.reuse_rules().iter().any(|r| r.applies)
+    }
+
     /// Value of applicable max-age (or heuristic equivalent) in seconds.
     ///
     /// This counts since response's `Date` - `Age`.
     ///
     /// For an up-to-date value, see `time_to_live()`.
     fn max_age(&self) -> Duration {
-        if !self.is_storable() || self.res_cc.contains_key("no-cache") {
+        if self.forbids_reuse() {
             return Duration::from_secs(0);
         }
-
-        // Shared responses with cookies are cacheable according to the RFC, but IMHO it'd be unwise to do so by default
-        // so this implementation requires explicit opt-in via public header
-        if self.opts.shared
-            && (self.res.contains_key("set-cookie")
-                && !self.res_cc.contains_key("public")
-                && !self.res_cc.contains_key("immutable"))
-        {
-            return Duration::from_secs(0);
-        }
-
-        if self.res.get_str("vary").map(str::trim) == Some("*") {
-            return Duration::from_secs(0);
+        let lifetime = self.freshness_lifetime().0;
+        match &self.overridden {
+            Some(o) => o.lifetime(lifetime),
+            None => lifetime,
         }
+    }
 
+    /// Freshness lifetime from the response's headers, ignoring rules that forbid reuse
+    fn freshness_lifetime(&self) -> (Duration, FreshnessSource) {
         if self.opts.shared {
-            if self.res_cc.contains_key("proxy-revalidate") {
-                return Duration::from_secs(0);
-            }
             // if a response includes the s-maxage directive, a shared cache recipient MUST ignore the Expires field.
-            if let Some(s_max) = self.res_cc.get("s-maxage").and_then(|v| v.as_ref()) {
-                return Duration::from_secs(s_max.parse().unwrap_or(0));
+            if self.res_cc.get("s-maxage").is_some() {
+                return (self.res_cc.s_maxage().unwrap_or_default(), FreshnessSource::SMaxAge);
             }
         }
 
         // If a response includes a Cache-Control field with the max-age directive, a recipient MUST ignore the Expires field.
-        if let Some(max_age) = self.res_cc.get("max-age").and_then(|v| v.as_ref()) {
-            return Duration::from_secs(max_age.parse().unwrap_or(0));
+        if self.res_cc.get("max-age").is_some() {
+            return (self.res_cc.max_age().unwrap_or_default(), FreshnessSource::MaxAge);
         }
 
-        let default_min_ttl = if self.res_cc.contains_key("immutable") {
+        let default_min_ttl = if self.res_cc.contains("immutable") {
             self.opts.immutable_min_time_to_live
         } else {
             Duration::from_secs(0)
         };
 
-        let server_date = self.raw_server_date();
-        if let Some(expires) = self.res.get_str("expires") {
-            return match OffsetDateTime::parse(expires, &Rfc2822) {
+        let at_least_min_ttl = |ttl: Duration, source| {
+            if ttl >= default_min_ttl {
+                (ttl, source)
+            } else {
+                (default_min_ttl, FreshnessSource::ImmutableMinimum)
+            }
+        };
+
+        let server_date = self.server_date();
+        if let Some(expires) = self.expires() {
+            return match self.parse_date(expires) {
                 // A cache recipient MUST interpret invalid date formats, especially the value "0", as representing a time in the past (i.e., "already expired").
-                Err(_) => Duration::from_secs(0),
-                Ok(expires) => {
-                    let expires = SystemTime::UNIX_EPOCH
-                        + Duration::from_secs(expires.unix_timestamp().max(0) as _);
-                    return default_min_ttl
-                        .max(expires.duration_since(server_date).unwrap_or_default());
+                None => (Duration::from_secs(0), FreshnessSource::Expires),
+                Some(expires) => {
+                    at_least_min_ttl(expires.duration_since(server_date).unwrap_or_default(), FreshnessSource::Expires)
                 }
             };
         }
 
-        if let Some(last_modified) = self.res.get_str("last-modified") {
-            if let Ok(last_modified) = OffsetDateTime::parse(last_modified, &Rfc2822) {
-                let last_modified = SystemTime::UNIX_EPOCH
-                    + Duration::from_secs(last_modified.unix_timestamp().max(0) as _);
+        let heuristic = &self.opts.heuristic_freshness;
+        if heuristic.applies_to(&self.uri) {
+            if let Some(last_modified) = self.date_header("last-modified") {
                 if let Ok(diff) = server_date.duration_since(last_modified) {
-                    let secs_left = diff.as_secs() as f64 * f64::from(self.opts.cache_heuristic);
-                    return default_min_ttl.max(Duration::from_secs(secs_left as _));
+                    let fraction = heuristic.fraction(self.opts.cache_heuristic, self.status, self.res.get_str("content-type"));
+                    let secs_left = diff.as_secs() as f64 * f64::from(fraction);
+                    return at_least_min_ttl(heuristic.limit(Duration::from_secs(secs_left as _)), FreshnessSource::LastModifiedHeuristic);
                 }
+            } else if let Some(lifetime) = heuristic.default_lifetime {
+                return at_least_min_ttl(heuristic.limit(lifetime), FreshnessSource::DefaultHeuristic);
             }
         }
 
-        default_min_ttl
+        if default_min_ttl.is_zero() {
+            (default_min_ttl, FreshnessSource::None)
+        } else {
+            (default_min_ttl, FreshnessSource::ImmutableMinimum)
+        }
     }
 
     /// Returns approximate time until the response becomes
@@ -640,11 +1348,11 @@ impl CachePolicy {
         // Clients MAY issue simple (non-subrange) GET requests with either weak validators or strong validators. Clients MUST NOT use weak validators in other forms of request.
         let forbids_weak_validators = self.method != Method::GET
             || headers.contains_key("accept-ranges")
+            || headers.contains_key("range")
             || headers.contains_key("if-match")
             || headers.contains_key("if-unmodified-since");
 
-        /* SHOULD send the Last-Modified value in non-subrange cache validation requests (using If-Modified-Since) if only a Last-Modified value has been provided by the origin server.
-        Note: This implementation does not understand partial responses (206) */
+        /* SHOULD send the Last-Modified value in non-subrange cache validation requests (using If-Modified-Since) if only a Last-Modified value has been provided by the origin server. */
         if forbids_weak_validators {
             headers.remove("if-modified-since");
 
@@ -679,20 +1387,216 @@ impl CachePolicy {
         parts
     }
 
+    /// Combining partial content (RFC 9111 section 3.4)
+    fn combine_ranges<Req: RequestLike, Res: ResponseLike>(
+        &self,
+        request: &Req,
+        response: &Res,
+        request_time: Option<SystemTime>,
+        response_time: SystemTime,
+    ) -> Option<AfterResponse> {
+        // both the stored and new response have validators, and all of those validators are strong
+        let old_validator = StrongValidator::of_response(&self.res)?;
+        if StrongValidator::of_response(response.headers()) != Some(old_validator) {
+            return None;
+        }
+        let mut combined = CachePolicy::from_details(
+            request.uri(),
+            request.method().clone(),
+            response.status(),
+            request.headers().clone(),
+            response.headers().clone(),
+            request_time,
+            response_time,
+            self.opts.clone(),
+        );
+        let new_range = ContentRange::parse(combined.res.get_str("content-range")?)?.range;
+        let complete_length = combined.complete_length();
+        let stored = match (&self.ranges, complete_length) {
+            (Some(ranges), _) => ranges.clone(),
+            (None, Some(len)) if len > 0 => vec![0..=len - 1],
+            (None, _) => return None,
+        };
+        let new_response = combined.validated_response(response_time);
+
+        let ranges = range::union(&stored, new_range);
+        let is_complete = complete_length.is_some_and(|len| ranges.len() == 1 && ranges[0] == (0..=len.saturating_sub(1)));
+        if let (true, Some(len)) = (is_complete, complete_length) {
+            combined.status = StatusCode::OK;
+            combined.res.remove("content-range");
+            combined.res.insert("content-length", HeaderValue::from(len));
+            combined.ranges = None;
+        } else {
+            combined.ranges = Some(ranges);
+        }
+        Some(AfterResponse::Combined(combined, new_response))
+    }
+
+    /// Checks whether the stale cached response can be used instead of an error
+    /// from the origin server, as allowed by the `stale-if-error` directive
+    /// ([RFC 5861](https://httpwg.org/specs/rfc5861.html)) of either the response or the request,
+    /// or by `Override::grace`.
+    ///
+    /// Returns headers for serving the cached response, or `None` if the error has to be passed on.
+    ///
+    /// Only connection failures and statuses 500, 502, 503 and 504 count as errors.
+    pub fn on_upstream_error<Req: RequestLike>(&self, req: &Req, error: UpstreamError, now: SystemTime) -> Option<http::response::Parts> {
+        if let UpstreamError::Status(status) = error {
+            if !is_upstream_error_status(status) {
+                return None;
+            }
+        }
+
+        // Revalidation is required even when disconnected
+        if self.request_mismatch(req).is_some() || self.forbids_reuse() || self.res_cc.must_revalidate() {
+            return None;
+        }
+
+        let staleness = self.age(now).saturating_sub(self.max_age());
+        let req_cc = parse_cache_control(req.headers().get_all("cache-control"));
+        let grace = self.overridden.as_ref().and_then(|o| o.grace);
+        let allows_stale = [self.res_cc.stale_if_error(), req_cc.stale_if_error(), grace]
+            .into_iter()
+            .flatten()
+            .any(|window| staleness <= window);
+        if !allows_stale {
+            return None;
+        }
+        Some(self.cached_response(now))
+    }
+
+    /// Returns `true` if this stored response has to be invalidated (removed from the cache, or marked as stale),
+    /// because of an unsafe request (like `POST` or `DELETE`) and its response.
+    ///
+    /// See [`invalidation_targets()`] for the URIs it checks.
+    pub fn is_invalidated_by<Req: RequestLike, Res: ResponseLike>(&self, req: &Req, res: &Res) -> bool {
+        let host = req.headers().get_str("host");
+        invalidation_targets(req, res)
+            .iter()
+            .any(|uri| invalidation::is_same_resource(uri, host, &self.uri, self.req.get_str("host")))
+    }
+
     /// Creates `CachePolicy` with information combined from the previews response,
     /// and the new revalidation response.
     ///
     /// Returns `{policy, modified}` where modified is a boolean indicating
     /// whether the response body has been modified, and old cached body can't be used.
+    ///
+    /// If the server responded with an error (500, 502, 503, 504), it returns `UpstreamError`
+    /// instead, and the cached response is left unchanged.
     pub fn after_response<Req: RequestLike, Res: ResponseLike>(
         &self,
         request: &Req,
         response: &Res,
         response_time: SystemTime,
+    ) -> AfterResponse {
+        self.after_response_details(request, response, None, response_time)
+    }
+
+    /// Like `after_response()`, but also takes the time when the revalidation request has been sent.
+    ///
+    /// The updated policy calculates the age of the response like `new_timed()`.
+    pub fn after_response_timed<Req: RequestLike, Res: ResponseLike>(
+        &self,
+        request: &Req,
+        response: &Res,
+        request_time: SystemTime,
+        response_time: SystemTime,
+    ) -> AfterResponse {
+        self.after_response_details(request, response, Some(request_time), response_time)
+    }
+
+    /// Updates the stored response to a `GET` request using a `200` response to a `HEAD` request for it ([RFC 9111 section 4.3.5](https://httpwg.org/specs/rfc9111.html#head.effects)).
+    ///
+    /// If the `HEAD` response has the same validators (`ETag` and `Last-Modified`) and `Content-Length`
+    /// as the stored response, its headers replace the stored ones (like in a 304 response), so it can become fresh again.
+    /// Returns `true` then.
+    ///
+    /// If they differ, the stored body is likely outdated, so the stored response becomes stale, and must be revalidated before use.
+    /// Responses to other requests, and other status codes don't change anything. Returns `false` in these cases.
+    pub fn after_head_response<Req: RequestLike, Res: ResponseLike>(
+        &mut self,
+        head_request: &Req,
+        head_response: &Res,
+        response_time: SystemTime,
+    ) -> bool {
+        if head_request.method() != Method::HEAD || self.method != Method::GET || head_response.status() != StatusCode::OK {
+            return false;
+        }
+        // update or invalidate each of its stored GET responses that could have been chosen for that request
+        match self.request_mismatch(head_request) {
+            None | Some(StaleReason::MethodMismatch) => {},
+            Some(_) => return false,
+        }
+
+        // matching values for any received validator fields (ETag and Last-Modified) and,
+        // if the HEAD response has a Content-Length header field, the value of Content-Length matches that of the stored response
+        let headers = head_response.headers();
+        let same = |name: &str| headers.get_str(name).is_none_or(|new| self.res.get_str(name).map(str::trim) == Some(new.trim()));
+        let same_length = headers.get_str("content-length")
+            .is_none_or(|len| len.trim().parse::<u64>().ok().is_some_and(|len| Some(len) == self.complete_length()));
+        let is_complete_or_partial = self.status == StatusCode::OK || self.ranges.is_some();
+        if !(is_complete_or_partial && same("etag") && same("last-modified") && same_length) {
+            // otherwise, the cache SHOULD consider the stored response to be stale
+            self.head_mismatch = true;
+            return false;
+        }
+
+        let mut updated = Self::from_details(
+            self.uri.clone(),
+            self.method.clone(),
+            self.status,
+            self.req.clone(),
+            self.updated_headers(headers),
+            None,
+            response_time,
+            self.opts.clone(),
+        );
+        updated.ranges = self.ranges.take();
+        *self = updated;
+        true
+    }
+
+    /// Stored headers with the values from a new response that doesn't have a body (RFC 9111 section 3.2)
+    fn updated_headers(&self, new_headers: &HeaderMap) -> HeaderMap {
+        let mut headers = HeaderMap::with_capacity(self.res.keys_len());
+        // use other header fields provided in the 304 (Not Modified) response to replace all instances
+        // of the corresponding header fields in the stored response.
+        for (header, old_value) in &self.res {
+            let header = header.clone();
+            if let Some(new_value) = new_headers.get(&header) {
+                if !EXCLUDED_FROM_REVALIDATION_UPDATE.contains(&header.as_str()) {
+                    headers.insert(header, new_value.clone());
+                    continue;
+                }
+            }
+            headers.insert(header, old_value.clone());
+        }
+        headers
+    }
+
+    fn after_response_details<Req: RequestLike, Res: ResponseLike>(
+        &self,
+        request: &Req,
+        response: &Res,
+        request_time: Option<SystemTime>,
+        response_time: SystemTime,
     ) -> AfterResponse {
         let response_headers = response.headers();
         let mut response_status = response.status();
 
+        if is_upstream_error_status(response_status) {
+            return AfterResponse::UpstreamError(
+                self.on_upstream_error(request, UpstreamError::Status(response_status), response_time),
+            );
+        }
+
+        if (self.opts.partial_content || self.ranges.is_some()) && response_status == StatusCode::PARTIAL_CONTENT {
+            if let Some(combined) = self.combine_ranges(request, response, request_time, response_time) {
+                return combined;
+            }
+        }
+
         let old_etag = self.res.get_str("etag").map(str::trim);
         let old_last_modified = self.res.get_str("last-modified").map(str::trim);
         let new_etag = response_headers.get_str("etag").map(str::trim);
@@ -703,7 +1607,7 @@ impl CachePolicy {
         let mut matches = false;
         if response.status() != StatusCode::NOT_MODIFIED {
             matches = false;
-        } else if new_etag.map_or(false, |etag| !etag.starts_with("W/")) {
+        } else if new_etag.is_some_and(|etag| !etag.starts_with("W/")) {
             // "All of the stored responses with the same strong validator are selected.
             // If none of the stored responses contain the same strong validator,
             // then the cache MUST NOT use the new response to update any stored responses."
@@ -730,18 +1634,10 @@ impl CachePolicy {
         }
 
         let new_response_headers = if matches {
-            let mut new_response_headers = HeaderMap::with_capacity(self.res.keys_len());
-            // use other header fields provided in the 304 (Not Modified) response to replace all instances
-            // of the corresponding header fields in the stored response.
-            for (header, old_value) in &self.res {
-                let header = header.clone();
-                if let Some(new_value) = response_headers.get(&header) {
-                    if !EXCLUDED_FROM_REVALIDATION_UPDATE.contains(&header.as_str()) {
-                        new_response_headers.insert(header, new_value.clone());
-                        continue;
-                    }
-                }
-                new_response_headers.insert(header, old_value.clone());
+            let mut new_response_headers = self.updated_headers(response_headers);
+            // the old Date would make the revalidated response look as old as the stored one
+            if request_time.is_some() && !response_headers.contains_key("date") {
+                new_response_headers.remove("date");
             }
             response_status = self.status;
             new_response_headers
@@ -749,16 +1645,20 @@ impl CachePolicy {
             response_headers.clone()
         };
 
-        let new_policy = CachePolicy::from_details(
+        let mut new_policy = CachePolicy::from_details(
             request.uri(),
             request.method().clone(),
             response_status,
             request.headers().clone(),
             new_response_headers,
+            request_time,
             response_time,
-            self.opts,
+            self.opts.clone(),
         );
-        let new_response = new_policy.cached_response(response_time);
+        if matches {
+            new_policy.ranges.clone_from(&self.ranges);
+        }
+        let new_response = new_policy.validated_response(response_time);
 
         if matches && response.status() == StatusCode::NOT_MODIFIED {
             AfterResponse::NotModified(new_policy, new_response)
@@ -769,11 +1669,44 @@ impl CachePolicy {
 }
 
 /// New policy and flags to act on `after_response()`
+#[non_exhaustive]
 pub enum AfterResponse {
     /// You can use the cached body! Make sure to use these updated headers
     NotModified(CachePolicy, http::response::Parts),
     /// You need to update the body in the cache
     Modified(CachePolicy, http::response::Parts),
+    /// The server responded with an error, so the cached response hasn't been updated.
+    ///
+    /// If `stale-if-error` (or `Override::grace`) allows it, these are the headers for serving the stale cached
+    /// body instead of the error. Otherwise the error response should be passed on.
+    UpstreamError(Option<http::response::Parts>),
+    /// The server sent a 206 (Partial Content) response with the same strong validator as the stored response,
+    /// so the new bytes can be combined with the stored ones (only with `CacheOptions::partial_content`, or after `mark_incomplete()`).
+    ///
+    /// Add the new response's body to the cached body, at the offset from its `Content-Range`.
+    /// The new policy's `stored_ranges()` tells which bytes are stored now. The headers are of the new response.
+    Combined(CachePolicy, http::response::Parts),
+    /// The server sent a 304 (Not Modified) response, but its validators don't match any of the stored responses
+    /// (only from `VariantSet::after_response()`), so nothing has been updated. These are the headers of the 304 response.
+    ///
+    /// If the client's request was conditional, the 304 may be for the client's own copy, and can be passed on.
+    /// Otherwise, send the request again without the `If-None-Match` and `If-Modified-Since` headers.
+    UnmatchedNotModified(http::response::Parts),
+}
+
+/// Reason why the origin server couldn't provide a response. See `CachePolicy::on_upstream_error()`
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum UpstreamError {
+    /// The server couldn't be reached, or the connection has failed or timed out
+    Unreachable,
+    /// The server responded with this status code
+    Status(StatusCode),
+}
+
+fn is_upstream_error_status(status: StatusCode) -> bool {
+    // RFC 5861: An error is any situation that would result in a 500, 502, 503, or 504 HTTP response status code being returned.
+    matches!(status.as_u16(), 500 | 502 | 503 | 504)
 }
 
 fn get_all_comma<'a>(
@@ -808,9 +1741,23 @@ fn join<'a>(parts: impl Iterator<Item = &'a str>) -> String {
 }
 
 /// Next action suggested after `before_request()`
+#[non_exhaustive]
 pub enum BeforeRequest {
     /// Good news! You can use it with body from the cache. No need to contact the server.
+    ///
+    /// Check the status before attaching the cached body. If the request has `If-None-Match` or `If-Modified-Since`
+    /// that the cached response satisfies, the status is `304 Not Modified`, and the response must be sent without the body.
     Fresh(http::response::Parts),
+    /// The response is stale, but `stale-while-revalidate` (or `Override::grace`) allows using it for now.
+    ///
+    /// Respond with the cached body, and send the revalidation request to the server
+    /// in the background. Then update the cache with `after_response()`.
+    StaleWhileRevalidate {
+        /// Headers of the cached response to use
+        response: http::response::Parts,
+        /// Send this request to the server (it has added revalidation headers when appropriate)
+        revalidation_request: http::request::Parts,
+    },
     /// You must send the request to the server first.
     Stale {
         /// Send this request to the server (it has added revalidation headers when appropriate)
@@ -818,12 +1765,80 @@ pub enum BeforeRequest {
         /// If `false`, request was for some other resource that isn't
         /// semantically the same as previously cached request+response
         matches: bool,
+        /// Why the cached response couldn't be used
+        reason: StaleReason,
     },
+    /// The request has `Cache-Control: only-if-cached`, but the cached response can't be used
+    /// (and neither `max-stale`, `stale-while-revalidate` nor an override's grace period allows it),
+    /// so the request must not be sent to the server. This includes requests for bytes that aren't
+    /// in a stored partial response (`StaleReason::RangeNotStored`).
+    ///
+    /// Respond with this `504 Gateway Timeout` instead. If you have other cached responses
+    /// for the same URL (e.g. due to `Vary`), check them first.
+    GatewayTimeout(http::response::Parts),
+}
+
+/// Why `before_request()` returned `Stale`
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum StaleReason {
+    /// The request is for a different URI
+    UriMismatch,
+    /// The request has a different `Host` header
+    HostMismatch,
+    /// The request uses a different method
+    MethodMismatch,
+    /// The response has `Vary: *`, which never matches
+    VaryStar,
+    /// A header nominated by `Vary` has a different value
+    VaryMismatch {
+        /// The differing header
+        name: HeaderName,
+        /// Value from the request the response has been cached for
+        stored: Option<HeaderValue>,
+        /// Value in the new request
+        presented: Option<HeaderValue>,
+    },
+    /// The request has `Cache-Control: no-cache` or `Pragma: no-cache`
+    RequestNoCache,
+    /// The response is older than the request's `max-age`
+    RequestMaxAge,
+    /// The response won't be fresh for as long as the request's `min-fresh`
+    RequestMinFresh,
+    /// The response is stored, but a rule makes it always stale (see `CachePolicy::explain()`)
+    NotReusable(CacheabilityRule),
+    /// The response is past its freshness lifetime
+    Expired,
+    /// The request needs bytes that aren't in the stored partial response
+    RangeNotStored,
+}
+
+impl StaleReason {
+    /// `true` if the request is for something else than the cached response
+    #[must_use]
+    pub fn is_mismatch(&self) -> bool {
+        matches!(self, Self::UriMismatch | Self::HostMismatch | Self::MethodMismatch | Self::VaryStar | Self::VaryMismatch { .. })
+    }
+
+    /// Value for the `fwd` parameter of the `Cache-Status` header ([RFC 9211](https://httpwg.org/specs/rfc9211.html))
+    #[must_use]
+    pub fn cache_status_fwd(&self) -> &'static str {
+        match self {
+            Self::UriMismatch | Self::HostMismatch => "uri-miss",
+            Self::MethodMismatch => "method",
+            Self::VaryStar | Self::VaryMismatch { .. } => "vary-miss",
+            Self::RequestNoCache | Self::RequestMaxAge | Self::RequestMinFresh => "request",
+            Self::NotReusable(_) | Self::Expired => "stale",
+            Self::RangeNotStored => "partial",
+        }
+    }
 }
 
 impl BeforeRequest {
     /// For backwards compatibility only.
     /// Don't forget to use request headers from `BeforeRequest::Fresh`
+    ///
+    /// `StaleWhileRevalidate` is not considered satisfied, since it needs a revalidation request.
     pub fn satisfies_without_revalidation(&self) -> bool {
         matches!(self, Self::Fresh(_))
     }
@@ -0,0 +1,105 @@
+//! Operator-defined exceptions to the caching rules of the origin server.
+
+use http::HeaderMap;
+use http::Method;
+use http::StatusCode;
+use http::Uri;
+use std::fmt;
+use std::panic::RefUnwindSafe;
+use std::time::Duration;
+
+/// Overrides what the origin server's headers say about caching of a response,
+/// e.g. to force a TTL on an API that doesn't send any cache headers.
+///
+/// Register overrides in `CacheOptions::policy_overrides`. They're evaluated when a `CachePolicy` is created
+/// (also by `after_response()`), and the returned `Override` is stored in the policy, so it stays in effect after serialization.
+///
+/// ```rust
+/// use http_cache_semantics::{CacheOptions, CachePolicy, Override, OverrideContext, PolicyOverride};
+/// use std::sync::Arc;
+/// use std::time::Duration;
+///
+/// #[derive(Debug)]
+/// struct ApiTtl;
+///
+/// impl PolicyOverride for ApiTtl {
+///     fn evaluate(&self, ctx: &OverrideContext<'_>) -> Option<Override> {
+///         ctx.uri.path().starts_with("/api/").then(|| Override {
+///             ttl: Some(Duration::from_secs(30)),
+///             ..Override::new("api-ttl")
+///         })
+///     }
+/// }
+///
+/// let options = CacheOptions { policy_overrides: vec![Arc::new(ApiTtl)], ..Default::default() };
+/// let req = http::Request::get("/api/items").body(()).unwrap();
+/// let res = http::Response::builder().body(()).unwrap();
+/// let policy = CachePolicy::new_options(&req, &res, std::time::SystemTime::now(), options);
+/// assert_eq!(policy.explain().freshness_lifetime, Duration::from_secs(30));
+/// ```
+pub trait PolicyOverride: fmt::Debug + Send + Sync + RefUnwindSafe {
+    /// How to treat the response differently, or `None` to follow its headers
+    fn evaluate(&self, ctx: &OverrideContext<'_>) -> Option<Override>;
+}
+
+/// The request and response that a `PolicyOverride` is evaluated for
+#[derive(Debug)]
+#[non_exhaustive]
+pub struct OverrideContext<'a> {
+    /// Method of the request
+    pub method: &'a Method,
+    /// URI of the request
+    pub uri: &'a Uri,
+    /// Status of the response
+    pub status: StatusCode,
+    /// Headers of the request
+    pub request_headers: &'a HeaderMap,
+    /// Headers of the response
+    pub response_headers: &'a HeaderMap,
+}
+
+/// Changes to the caching of a response, returned by a `PolicyOverride`
+///
+/// Durations are serialized as whole seconds.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct Override {
+    /// Identifies the override in `CachePolicy::explain()`, and in the `Cache-Override` header of cached responses (unless it's empty)
+    pub name: String,
+    /// `Some(true)` stores the response despite `no-store` or `private` in the response, or a lack of explicit freshness.
+    /// The other rules of `is_storable()` still apply, e.g. the request method must be cacheable,
+    /// and a 206 response requires `CacheOptions::partial_content`.
+    ///
+    /// `Some(false)` never stores it.
+    pub storable: Option<bool>,
+    /// Freshness lifetime to use instead of the one from `max-age`, `Expires` or heuristics.
+    ///
+    /// Rules that make the response always stale (like `no-cache`) still apply.
+    #[cfg_attr(feature = "serde", serde(with = "crate::secs"))]
+    pub ttl: Option<Duration>,
+    /// Upper limit of the freshness lifetime
+    #[cfg_attr(feature = "serde", serde(with = "crate::secs"))]
+    pub max_ttl: Option<Duration>,
+    /// How long the response can be used after it becomes stale, while it's revalidated in the background,
+    /// or when the origin server fails. Replaces `stale-while-revalidate`, and is used in addition to `stale-if-error`.
+    #[cfg_attr(feature = "serde", serde(with = "crate::secs"))]
+    pub grace: Option<Duration>,
+    /// Response headers that aren't stored, e.g. `Set-Cookie`.
+    ///
+    /// They're removed before the response is checked, so without `Set-Cookie` the response can be stored by a shared cache.
+    pub strip_headers: Vec<String>,
+}
+
+impl Override {
+    /// An override that doesn't change anything yet
+    pub fn new(name: impl Into<String>) -> Self {
+        Self { name: name.into(), ..Self::default() }
+    }
+
+    /// The freshness lifetime changed by `ttl` and `max_ttl`
+    pub(crate) fn lifetime(&self, lifetime: Duration) -> Duration {
+        let lifetime = self.ttl.unwrap_or(lifetime);
+        self.max_ttl.map_or(lifetime, |max| lifetime.min(max))
+    }
+}
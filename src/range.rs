@@ -0,0 +1,168 @@
+//! Range requests and partial responses ([RFC 9110 section 14](https://httpwg.org/specs/rfc9110.html#range.requests)).
+
+use crate::GetHeaderStr;
+use crate::HttpDate;
+use http::HeaderMap;
+use http::HeaderValue;
+use std::fmt;
+use std::ops::RangeInclusive;
+
+/// Value of a `Content-Range` header with a single byte range, e.g. `bytes 0-499/1234`.
+///
+/// ```rust
+/// use http_cache_semantics::ContentRange;
+///
+/// let cr = ContentRange::parse("bytes 500-999/*").unwrap();
+/// assert_eq!(cr.range, 500..=999);
+/// assert_eq!(cr.complete_length, None);
+/// assert_eq!(cr.to_string(), "bytes 500-999/*");
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ContentRange {
+    /// Offsets of the first and the last byte in the response body
+    pub range: RangeInclusive<u64>,
+    /// Length of the complete representation, if known
+    pub complete_length: Option<u64>,
+}
+
+impl ContentRange {
+    /// Parses `bytes first-last/complete-length`. The complete length can be `*`.
+    ///
+    /// Unsatisfied ranges (`bytes */1234`) and other range units aren't supported.
+    #[must_use]
+    pub fn parse(value: &str) -> Option<Self> {
+        let (unit, rest) = value.trim().split_once(' ')?;
+        if !unit.eq_ignore_ascii_case("bytes") {
+            return None;
+        }
+        let (range, complete_length) = rest.trim_start().split_once('/')?;
+        let (first, last) = range.split_once('-')?;
+        let first = parse_digits(first)?;
+        let last = parse_digits(last)?;
+        let complete_length = match complete_length {
+            "*" => None,
+            len => Some(parse_digits(len)?),
+        };
+        if first > last || complete_length.is_some_and(|len| last >= len) {
+            return None;
+        }
+        Some(Self { range: first..=last, complete_length })
+    }
+}
+
+impl fmt::Display for ContentRange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "bytes {}-{}/", self.range.start(), self.range.end())?;
+        match self.complete_length {
+            Some(len) => write!(f, "{len}"),
+            None => f.write_str("*"),
+        }
+    }
+}
+
+/// A single range from the `Range` request header
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub(crate) enum RangeSpec {
+    /// `first-` or `first-last`
+    FromTo(u64, Option<u64>),
+    /// `-suffix_length`
+    Suffix(u64),
+}
+
+impl RangeSpec {
+    /// Only a single byte range is supported. Multiple ranges need a multipart response.
+    pub(crate) fn from_request(headers: &HeaderMap) -> Option<Self> {
+        let mut values = headers.get_all("range").iter();
+        let value = values.next()?.to_str().ok()?;
+        if values.next().is_some() {
+            return None;
+        }
+        let (unit, spec) = value.trim().split_once('=')?;
+        if !unit.trim().eq_ignore_ascii_case("bytes") || spec.contains(',') {
+            return None;
+        }
+        let (first, last) = spec.trim().split_once('-')?;
+        let (first, last) = (first.trim(), last.trim());
+        if first.is_empty() {
+            return Some(Self::Suffix(parse_digits(last).filter(|&n| n > 0)?));
+        }
+        let first = parse_digits(first)?;
+        let last = if last.is_empty() { None } else { Some(parse_digits(last).filter(|&l| l >= first)?) };
+        Some(Self::FromTo(first, last))
+    }
+
+    /// Absolute byte range, if it can be known and satisfied
+    pub(crate) fn resolve(self, complete_length: Option<u64>) -> Option<RangeInclusive<u64>> {
+        match (self, complete_length) {
+            (Self::FromTo(first, last), Some(len)) => {
+                (first < len).then(|| first..=last.unwrap_or(len - 1).min(len - 1))
+            },
+            (Self::FromTo(first, Some(last)), None) => Some(first..=last),
+            (Self::Suffix(n), Some(len)) if len > 0 => Some(len.saturating_sub(n)..=len - 1),
+            _ => None,
+        }
+    }
+}
+
+/// Adds the `new` range to sorted, non-overlapping ranges, merging adjacent ones
+pub(crate) fn union(ranges: &[RangeInclusive<u64>], new: RangeInclusive<u64>) -> Vec<RangeInclusive<u64>> {
+    let mut all: Vec<_> = ranges.iter().cloned().chain([new]).collect();
+    all.sort_by_key(|r| *r.start());
+    let mut out: Vec<RangeInclusive<u64>> = Vec::with_capacity(all.len());
+    for r in all {
+        match out.last_mut() {
+            Some(last) if *r.start() <= last.end().saturating_add(1) => {
+                if r.end() > last.end() {
+                    *last = *last.start()..=*r.end();
+                }
+            },
+            _ => out.push(r),
+        }
+    }
+    out
+}
+
+/// Strong validator of a response: a strong `ETag`, or a strong `Last-Modified` date (RFC 9110 section 8.8.2.2)
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) enum StrongValidator<'a> {
+    ETag(&'a str),
+    LastModified(HttpDate),
+}
+
+impl<'a> StrongValidator<'a> {
+    pub(crate) fn of_response(headers: &'a HeaderMap) -> Option<Self> {
+        if let Some(etag) = headers.get_str("etag") {
+            let etag = etag.trim();
+            return (!etag.starts_with("W/")).then_some(Self::ETag(etag));
+        }
+        let last_modified = HttpDate::parse(headers.get_str("last-modified")?).ok()?;
+        let date = HttpDate::parse(headers.get_str("date")?).ok()?;
+        // the date is strong if it's at least one second before the response's Date
+        (last_modified.unix_timestamp() < date.unix_timestamp()).then_some(Self::LastModified(last_modified))
+    }
+
+    pub(crate) fn to_header_value(&self) -> HeaderValue {
+        match self {
+            Self::ETag(etag) => HeaderValue::from_str(etag).unwrap(),
+            Self::LastModified(date) => HeaderValue::from_str(&date.to_string()).unwrap(),
+        }
+    }
+
+    /// Evaluates `If-Range` (RFC 9110 section 13.1.5). Weak entity-tags never match.
+    pub(crate) fn matches_if_range(&self, if_range: &str) -> bool {
+        let if_range = if_range.trim();
+        match self {
+            Self::ETag(etag) => if_range == *etag,
+            Self::LastModified(date) => {
+                !(if_range.starts_with('"') || if_range.starts_with("W/")) && HttpDate::parse(if_range).ok() == Some(*date)
+            },
+        }
+    }
+}
+
+fn parse_digits(s: &str) -> Option<u64> {
+    if s.is_empty() || !s.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    s.parse().ok()
+}
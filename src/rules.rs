@@ -0,0 +1,233 @@
+//! Declarative caching rules, e.g. loaded from a config file.
+
+use crate::GetHeaderStr;
+use crate::Override;
+use crate::OverrideContext;
+use crate::PolicyOverride;
+use crate::VaryNormalization;
+use http::HeaderMap;
+use http::Uri;
+use http::uri::Authority;
+#[cfg(feature = "serde")]
+use std::time::Duration;
+
+/// Applies an `Override` to responses that match all of its conditions.
+///
+/// Rules are in `CacheOptions::rules`, so they can be deserialized with the rest of the options.
+/// The first rule that matches is used, after the `CacheOptions::policy_overrides`. Durations are in seconds,
+/// and the name of the rule is the name of the override.
+///
+/// In TOML:
+///
+/// ```toml
+/// [[rules]]
+/// name = "api"
+/// match = { host = "api.example.com", path = "/v1/*", methods = ["GET"], statuses = [200] }
+/// ttl = 30
+/// grace = 300
+/// vary_normalization = ["accept-encoding"]
+/// strip_headers = ["set-cookie"]
+///
+/// [[rules]]
+/// name = "no-private"
+/// match = { path = "/account/*" }
+/// storable = false
+/// ```
+///
+/// The same in JSON:
+///
+/// ```rust
+/// # #[cfg(feature = "serde")] {
+/// use http_cache_semantics::CacheOptions;
+/// use std::time::Duration;
+///
+/// let options: CacheOptions = serde_json::from_str(r#"{"rules": [
+///     {
+///         "name": "api",
+///         "match": {"host": "api.example.com", "path": "/v1/*", "methods": ["GET"], "statuses": [200]},
+///         "ttl": 30, "grace": 300,
+///         "vary_normalization": ["accept-encoding"],
+///         "strip_headers": ["set-cookie"]
+///     },
+///     {"name": "no-private", "match": {"path": "/account/*"}, "storable": false}
+/// ]}"#).unwrap();
+/// assert_eq!(options.rules[0].apply.ttl, Some(Duration::from_secs(30)));
+/// # }
+/// ```
+///
+/// Unknown keys are rejected, so that a misspelled condition doesn't make the rule match everything.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(from = "RuleFields", into = "RuleFields"))]
+pub struct Rule {
+    /// Which responses the rule applies to
+    pub conditions: RuleMatch,
+    /// Normalization of request headers listed in `Vary`, which takes precedence over `CacheOptions::vary_normalizers`.
+    ///
+    /// It's used for all requests that match the `host` and `path` conditions, regardless of the other conditions,
+    /// because `CacheOptions::secondary_key_for_request()` has to pick it before the response is known.
+    pub vary_normalization: Vec<VaryNormalization>,
+    /// What the rule changes
+    pub apply: Override,
+}
+
+/// Serialized `Rule`, with the fields of `Override` at the top level.
+///
+/// `#[serde(flatten)]` can't be combined with `deny_unknown_fields`, so they're listed here.
+#[cfg(feature = "serde")]
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct RuleFields {
+    name: String,
+    #[serde(rename = "match")]
+    conditions: RuleMatch,
+    vary_normalization: Vec<VaryNormalization>,
+    storable: Option<bool>,
+    #[serde(with = "crate::secs")]
+    ttl: Option<Duration>,
+    #[serde(with = "crate::secs")]
+    max_ttl: Option<Duration>,
+    #[serde(with = "crate::secs")]
+    grace: Option<Duration>,
+    strip_headers: Vec<String>,
+}
+
+#[cfg(feature = "serde")]
+impl Default for RuleFields {
+    fn default() -> Self {
+        Rule::default().into()
+    }
+}
+
+#[cfg(feature = "serde")]
+impl From<RuleFields> for Rule {
+    fn from(f: RuleFields) -> Self {
+        Self {
+            conditions: f.conditions,
+            vary_normalization: f.vary_normalization,
+            apply: Override {
+                name: f.name,
+                storable: f.storable,
+                ttl: f.ttl,
+                max_ttl: f.max_ttl,
+                grace: f.grace,
+                strip_headers: f.strip_headers,
+            },
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl From<Rule> for RuleFields {
+    fn from(rule: Rule) -> Self {
+        let Override { name, storable, ttl, max_ttl, grace, strip_headers } = rule.apply;
+        Self {
+            name,
+            conditions: rule.conditions,
+            vary_normalization: rule.vary_normalization,
+            storable,
+            ttl,
+            max_ttl,
+            grace,
+            strip_headers,
+        }
+    }
+}
+
+/// Conditions of a `Rule`. Conditions that aren't set match everything.
+///
+/// In patterns, `*` matches any number of characters (including `/`), and `?` matches one character.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default, deny_unknown_fields))]
+pub struct RuleMatch {
+    /// Pattern of the request's host, without the port, e.g. `*.example.com`. Case-insensitive.
+    ///
+    /// The host is taken from the request's URI, or from its `Host` header.
+    pub host: Option<String>,
+    /// Pattern of the request's path, without the query string, e.g. `/static/*.js`
+    pub path: Option<String>,
+    /// Request methods, e.g. `["GET", "HEAD"]`. Empty matches all methods.
+    pub methods: Vec<String>,
+    /// Response status codes. Empty matches all statuses.
+    pub statuses: Vec<u16>,
+    /// Pattern of the media type in the response's `Content-Type`, without parameters, e.g. `image/*`. Case-insensitive.
+    pub content_type: Option<String>,
+}
+
+impl RuleMatch {
+    /// All conditions are met
+    pub(crate) fn matches(&self, ctx: &OverrideContext<'_>) -> bool {
+        if !self.matches_uri(ctx.uri, ctx.request_headers) {
+            return false;
+        }
+        if !self.methods.is_empty() && !self.methods.iter().any(|m| m.eq_ignore_ascii_case(ctx.method.as_str())) {
+            return false;
+        }
+        if !self.statuses.is_empty() && !self.statuses.contains(&ctx.status.as_u16()) {
+            return false;
+        }
+        if let Some(pattern) = &self.content_type {
+            let media_type = ctx.response_headers.get_str("content-type")
+                .and_then(|t| t.split(';').next())
+                .map(str::trim);
+            if !media_type.is_some_and(|t| glob_matches(pattern, t, true)) {
+                return false;
+            }
+        }
+        true
+    }
+
+    /// The `host` and `path` conditions are met
+    pub(crate) fn matches_uri(&self, uri: &Uri, request_headers: &HeaderMap) -> bool {
+        if let Some(pattern) = &self.path {
+            if !glob_matches(pattern, uri.path(), false) {
+                return false;
+            }
+        }
+        if let Some(pattern) = &self.host {
+            let host = match uri.host() {
+                Some(host) => Some(host.to_owned()),
+                None => request_headers.get_str("host")
+                    .and_then(|h| h.parse::<Authority>().ok())
+                    .map(|a| a.host().to_owned()),
+            };
+            if !host.is_some_and(|host| glob_matches(pattern, &host, true)) {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+impl PolicyOverride for Rule {
+    fn evaluate(&self, ctx: &OverrideContext<'_>) -> Option<Override> {
+        self.conditions.matches(ctx).then(|| self.apply.clone())
+    }
+}
+
+/// Matches `*` and `?` wildcards, backtracking only to the last `*`
+fn glob_matches(pattern: &str, text: &str, ignore_case: bool) -> bool {
+    let (pattern, text) = (pattern.as_bytes(), text.as_bytes());
+    let same = |p: u8, t: u8| p == b'?' || p == t || (ignore_case && p.eq_ignore_ascii_case(&t));
+    let (mut p, mut t) = (0, 0);
+    // position of the last `*`, and of the text it has matched up to
+    let mut star = None;
+    while t < text.len() {
+        if p < pattern.len() && pattern[p] == b'*' {
+            star = Some((p, t));
+            p += 1;
+        } else if p < pattern.len() && same(pattern[p], text[t]) {
+            p += 1;
+            t += 1;
+        } else if let Some((star_p, star_t)) = star {
+            // let the `*` match one more character
+            p = star_p + 1;
+            t = star_t + 1;
+            star = Some((star_p, t));
+        } else {
+            return false;
+        }
+    }
+    pattern[p..].iter().all(|&c| c == b'*')
+}
@@ -0,0 +1,289 @@
+//! Multiple stored responses for the same URI ([RFC 9111 section 4.1](https://httpwg.org/specs/rfc9111.html#caching.negotiated.responses)).
+
+use crate::get_all_comma;
+use crate::join;
+use crate::AfterResponse;
+use crate::BeforeRequest;
+use crate::CachePolicy;
+use crate::GetHeaderStr;
+use crate::RequestLike;
+use crate::ResponseLike;
+use crate::StaleReason;
+use http::HeaderValue;
+use http::Method;
+use http::Response;
+use http::StatusCode;
+use std::time::SystemTime;
+
+/// Stored responses for one URI that differ by headers listed in `Vary`,
+/// e.g. compressed and uncompressed, or in different languages.
+///
+/// Each `CachePolicy` is stored together with your data `T`, such as the response body or a key of it.
+///
+/// A single `CachePolicy` only knows its own response, so it can't revalidate other representations,
+/// or tell which of them a 304 Not Modified response is for. `VariantSet` can.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(try_from = "StoredVariants<T>"))]
+pub struct VariantSet<T> {
+    /// Never empty
+    variants: Vec<(CachePolicy, T)>,
+}
+
+/// Deserialized `VariantSet`, before checking that it's not empty
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+struct StoredVariants<T> {
+    variants: Vec<(CachePolicy, T)>,
+}
+
+#[cfg(feature = "serde")]
+impl<T> TryFrom<StoredVariants<T>> for VariantSet<T> {
+    type Error = &'static str;
+
+    fn try_from(stored: StoredVariants<T>) -> Result<Self, Self::Error> {
+        if stored.variants.is_empty() {
+            return Err("VariantSet must have at least one stored response");
+        }
+        Ok(Self { variants: stored.variants })
+    }
+}
+
+impl<T> VariantSet<T> {
+    /// A set with one stored response
+    pub fn new(policy: CachePolicy, data: T) -> Self {
+        Self { variants: vec![(policy, data)] }
+    }
+
+    /// Adds a stored response.
+    ///
+    /// If a response for the same request (the same URI, and the same values of headers listed in `Vary`) has been stored already,
+    /// it's replaced, and its data is returned.
+    pub fn insert(&mut self, policy: CachePolicy, data: T) -> Option<T> {
+        let new_req = policy.request_from_headers(policy.req.clone());
+        let existing = self.variants.iter().position(|(old, _)| {
+            old.request_mismatch(&new_req).is_none()
+                && policy.request_mismatch(&old.request_from_headers(old.req.clone())).is_none()
+        });
+        match existing {
+            Some(i) => Some(std::mem::replace(&mut self.variants[i], (policy, data)).1),
+            None => {
+                self.variants.push((policy, data));
+                None
+            },
+        }
+    }
+
+    /// Removes the stored response that `select()` would return for the request, and returns its data.
+    ///
+    /// The set can't be empty, so it's returned only if it still has stored responses.
+    /// If none of them matches the request, the set is returned unchanged.
+    pub fn remove<Req: RequestLike>(mut self, req: &Req) -> (Option<Self>, Option<T>) {
+        let Some(i) = self.select_index(req) else {
+            return (Some(self), None);
+        };
+        let (_, data) = self.variants.remove(i);
+        let rest = (!self.variants.is_empty()).then_some(self);
+        (rest, Some(data))
+    }
+
+    /// Number of stored responses
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.variants.len()
+    }
+
+    /// Always `false`, since the set can't be empty
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        false
+    }
+
+    /// All stored responses, in the order they were added
+    pub fn iter(&self) -> impl Iterator<Item = (&CachePolicy, &T)> {
+        self.variants.iter().map(|(p, d)| (p, d))
+    }
+
+    /// The stored response that matches the request (the same URI, method, and headers listed in `Vary`).
+    ///
+    /// If several match, the most recent one (by `Date`) is selected. Its freshness is not checked.
+    pub fn select<Req: RequestLike>(&self, req: &Req) -> Option<(&CachePolicy, &T)> {
+        let i = self.select_index(req)?;
+        let (p, d) = &self.variants[i];
+        Some((p, d))
+    }
+
+    fn select_index<Req: RequestLike>(&self, req: &Req) -> Option<usize> {
+        most_recent(&self.variants, (0..self.variants.len()).filter(|&i| self.variants[i].0.request_mismatch(req).is_none()))
+    }
+
+    /// The stored response that matches the request, or else the most recent one.
+    ///
+    /// The `bool` is `true` if it matches.
+    fn select_or_most_recent<Req: RequestLike>(&self, req: &Req) -> (usize, bool) {
+        match self.select_index(req) {
+            Some(i) => (i, true),
+            // the first one is the most recent until a more recent one is found, since the set can't be empty
+            None => ((1..self.variants.len()).fold(0, |best, i| {
+                if recency(&self.variants[i].0) >= recency(&self.variants[best].0) { i } else { best }
+            }), false),
+        }
+    }
+
+    /// Like `CachePolicy::before_request()`, but using the stored response that matches the request.
+    ///
+    /// Returns data of the selected stored response, if there is one matching the request.
+    /// Requests for revalidation list `ETag`s of all stored responses in `If-None-Match`,
+    /// so that the server can choose any of them, and `after_response()` can update the right one.
+    pub fn before_request<Req: RequestLike>(&self, req: &Req, now: SystemTime) -> (BeforeRequest, Option<&T>) {
+        let (i, selected) = self.select_or_most_recent(req);
+        let (policy, data) = &self.variants[i];
+        let data = selected.then_some(data);
+        match policy.before_request(req, now) {
+            BeforeRequest::Stale { mut request, matches, reason } => {
+                if reason != StaleReason::RangeNotStored {
+                    self.add_etags(&mut request);
+                }
+                (BeforeRequest::Stale { request, matches, reason }, data)
+            },
+            BeforeRequest::StaleWhileRevalidate { response, mut revalidation_request } => {
+                self.add_etags(&mut revalidation_request);
+                (BeforeRequest::StaleWhileRevalidate { response, revalidation_request }, data)
+            },
+            other => (other, data),
+        }
+    }
+
+    /// Union of `If-None-Match` of the request and `ETag`s of stored responses for it (RFC 9111 section 4.3.2)
+    fn add_etags(&self, request: &mut http::request::Parts) {
+        // Clients MUST NOT use weak validators in other forms of request than simple GET
+        let allows_weak = request.method == Method::GET
+            && !request.headers.contains_key("range")
+            && !request.headers.contains_key("if-match")
+            && !request.headers.contains_key("if-unmodified-since");
+        let stored = self.variants.iter()
+            .filter(|(p, _)| p.method == request.method && request.uri == p.uri)
+            .filter_map(|(p, _)| p.res.get_str("etag"));
+        let mut etags: Vec<&str> = Vec::new();
+        for etag in get_all_comma(request.headers.get_all("if-none-match")).chain(stored) {
+            let etag = etag.trim();
+            if (allows_weak || !etag.starts_with("W/")) && !etags.contains(&etag) {
+                etags.push(etag);
+            }
+        }
+        if !etags.is_empty() {
+            request.headers.insert("if-none-match", HeaderValue::from_str(&join(etags.into_iter())).unwrap());
+        }
+    }
+
+    /// Like `CachePolicy::after_response()`, but updates the stored responses selected by the validator of a 304 response.
+    ///
+    /// For `NotModified`, `Combined` and `UpstreamError` with a stale response, the set is updated already,
+    /// and the data of the stored response to use is returned (for `Combined`, add the new bytes to it).
+    /// For `Modified`, add the new response with `insert()`.
+    ///
+    /// A 304 response that isn't for any of the stored responses returns `UnmatchedNotModified`, and nothing is updated.
+    pub fn after_response<Req: RequestLike, Res: ResponseLike>(
+        &mut self,
+        req: &Req,
+        res: &Res,
+        response_time: SystemTime,
+    ) -> (AfterResponse, Option<&mut T>) {
+        if res.status() == StatusCode::NOT_MODIFIED {
+            let selected = self.select_for_update(res);
+            let mut result = None;
+            for i in selected {
+                let policy = &self.variants[i].0;
+                // revalidation of a stored response doesn't change which requests it's for, unless it's for this request
+                let update = if policy.request_mismatch(req).is_none() {
+                    policy.after_response(req, res, response_time)
+                } else {
+                    policy.after_response(&policy.request_from_headers(policy.req.clone()), res, response_time)
+                };
+                if let AfterResponse::NotModified(new_policy, parts) = update {
+                    self.variants[i].0 = new_policy.clone();
+                    if result.is_none() {
+                        result = Some((AfterResponse::NotModified(new_policy, parts), i));
+                    }
+                }
+            }
+            if let Some((after, i)) = result {
+                return (after, Some(&mut self.variants[i].1));
+            }
+            // the cache MUST NOT use the new response to update any stored responses
+            let mut parts = Response::new(()).into_parts().0;
+            parts.status = res.status();
+            parts.headers = res.headers().clone();
+            return (AfterResponse::UnmatchedNotModified(parts), None);
+        }
+
+        let (i, selected) = self.select_or_most_recent(req);
+        match self.variants[i].0.after_response(req, res, response_time) {
+            AfterResponse::Combined(policy, parts) if selected => {
+                self.variants[i].0 = policy.clone();
+                (AfterResponse::Combined(policy, parts), Some(&mut self.variants[i].1))
+            },
+            AfterResponse::UpstreamError(Some(parts)) if selected => {
+                (AfterResponse::UpstreamError(Some(parts)), Some(&mut self.variants[i].1))
+            },
+            AfterResponse::UpstreamError(_) => (AfterResponse::UpstreamError(None), None),
+            other => (other, None),
+        }
+    }
+
+    /// Like `CachePolicy::after_head_response()`, for all stored responses that could have been selected for the `HEAD` request.
+    ///
+    /// Returns `true` if any of them has been updated.
+    pub fn after_head_response<Req: RequestLike, Res: ResponseLike>(&mut self, head_request: &Req, head_response: &Res, response_time: SystemTime) -> bool {
+        let mut updated = false;
+        for (policy, _) in &mut self.variants {
+            updated |= policy.after_head_response(head_request, head_response, response_time);
+        }
+        updated
+    }
+
+    /// Stored responses that a 304 response is for (RFC 9111 section 4.3.4), the most recent first
+    fn select_for_update<Res: ResponseLike>(&self, res: &Res) -> Vec<usize> {
+        let new_etag = res.headers().get_str("etag").map(str::trim);
+        let new_last_modified = res.headers().get_str("last-modified").map(str::trim);
+        let old_etag = |i: usize| self.variants[i].0.res.get_str("etag").map(str::trim);
+        let old_last_modified = |i: usize| self.variants[i].0.res.get_str("last-modified").map(str::trim);
+        let all = 0..self.variants.len();
+
+        match new_etag {
+            // All of the stored responses with the same strong validator are identified.
+            Some(new) if !new.starts_with("W/") => {
+                let mut selected: Vec<_> = all.filter(|&i| old_etag(i).is_some_and(|old| old.trim_start_matches("W/") == new)).collect();
+                selected.sort_by_key(|&i| std::cmp::Reverse(recency(&self.variants[i].0)));
+                selected
+            },
+            // If the new response contains a weak validator and that validator corresponds to one of the cache's stored responses,
+            // then the most recent of those matching stored responses is identified.
+            Some(new) => {
+                let new = new.trim_start_matches("W/");
+                most_recent(&self.variants, all.filter(|&i| old_etag(i).is_some_and(|old| old.trim_start_matches("W/") == new))).into_iter().collect()
+            },
+            None if new_last_modified.is_some() => {
+                most_recent(&self.variants, all.filter(|&i| old_last_modified(i) == new_last_modified)).into_iter().collect()
+            },
+            // If the new response does not include any form of validator, and there is only one stored response,
+            // and that stored response also lacks a validator, then that stored response is identified.
+            None => {
+                if self.variants.len() == 1 && old_etag(0).is_none() && old_last_modified(0).is_none() {
+                    vec![0]
+                } else {
+                    Vec::new()
+                }
+            },
+        }
+    }
+}
+
+/// When the response has been generated, for picking the most recent one
+fn recency(policy: &CachePolicy) -> (SystemTime, SystemTime) {
+    (policy.raw_server_date(), policy.response_time)
+}
+
+fn most_recent<T>(variants: &[(CachePolicy, T)], indices: impl Iterator<Item = usize>) -> Option<usize> {
+    indices.max_by_key(|&i| recency(&variants[i].0))
+}
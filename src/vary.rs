@@ -0,0 +1,333 @@
+//! Normalization of request headers listed in `Vary` ([RFC 9111 section 4.1](https://httpwg.org/specs/rfc9111.html#caching.negotiated.responses)).
+
+use crate::get_all_comma;
+use http::HeaderMap;
+use std::fmt;
+use std::panic::RefUnwindSafe;
+
+/// Makes equivalent values of a request header compare equal when matching `Vary`.
+///
+/// For example, `Accept-Encoding: gzip, br` and `Accept-Encoding: br,gzip` select the same response,
+/// but compared byte for byte they'd need two separate cache entries.
+///
+/// Register normalizers in `CacheOptions::vary_normalizers`. The origin server must respond to
+/// requests with equal normalized values in the same way, otherwise wrong responses will be reused.
+pub trait VaryNormalizer: fmt::Debug + Send + Sync + RefUnwindSafe {
+    /// Lowercase name of the request header this normalizes, e.g. `accept-encoding`
+    fn header_name(&self) -> &str;
+
+    /// The value to compare instead of the header's value.
+    ///
+    /// `value` is `None` if the request doesn't have the header. Return `None` to treat the value as absent.
+    fn normalize(&self, value: Option<&str>) -> Option<String>;
+}
+
+/// Reduces `Accept-Encoding` to the single content coding the cache prefers to serve.
+///
+/// Requests that accept none of the `codings` normalize to an absent header, since they all get the unencoded response.
+///
+/// ```rust
+/// use http_cache_semantics::{AcceptEncodingNormalizer, VaryNormalizer};
+///
+/// let n = AcceptEncodingNormalizer::new(["br", "gzip"]);
+/// assert_eq!(n.normalize(Some("gzip, deflate, br")), Some("br".into()));
+/// assert_eq!(n.normalize(Some("br;q=0, *")), Some("gzip".into()));
+/// assert_eq!(n.normalize(Some("deflate")), None);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AcceptEncodingNormalizer {
+    /// Lowercase content codings that the cache can serve, the most preferred first
+    pub codings: Vec<String>,
+}
+
+impl AcceptEncodingNormalizer {
+    /// Codings that the cache can serve, the most preferred first
+    pub fn new<S: Into<String>>(codings: impl IntoIterator<Item = S>) -> Self {
+        Self {
+            codings: codings.into_iter().map(|c| c.into().to_ascii_lowercase()).collect(),
+        }
+    }
+}
+
+/// `br` and `gzip`
+impl Default for AcceptEncodingNormalizer {
+    fn default() -> Self {
+        Self::new(["br", "gzip"])
+    }
+}
+
+impl VaryNormalizer for AcceptEncodingNormalizer {
+    fn header_name(&self) -> &str {
+        "accept-encoding"
+    }
+
+    fn normalize(&self, value: Option<&str>) -> Option<String> {
+        let accepted = weighted_items(value?);
+        let quality = |coding: &str| {
+            accepted.iter().find(|(c, _)| c == coding)
+                .or_else(|| accepted.iter().find(|(c, _)| c == "*"))
+                .map_or(0., |&(_, q)| q)
+        };
+        self.codings.iter().find(|c| quality(c) > 0.).cloned()
+    }
+}
+
+/// Sorts language ranges of `Accept-Language` by their q-value, and drops the ones that aren't acceptable.
+///
+/// ```rust
+/// use http_cache_semantics::{AcceptLanguageNormalizer, VaryNormalizer};
+///
+/// let n = AcceptLanguageNormalizer;
+/// assert_eq!(n.normalize(Some("fr;q=0.5, en-US, de;q=0")), Some("en-us,fr".into()));
+/// assert_eq!(n.normalize(Some("en-us , fr;q=0.50")), Some("en-us,fr".into()));
+/// ```
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub struct AcceptLanguageNormalizer;
+
+impl VaryNormalizer for AcceptLanguageNormalizer {
+    fn header_name(&self) -> &str {
+        "accept-language"
+    }
+
+    fn normalize(&self, value: Option<&str>) -> Option<String> {
+        by_quality(value?)
+    }
+}
+
+/// Sorts media ranges of `Accept` by their q-value, and drops the ones that aren't acceptable.
+///
+/// Media type parameters other than `q` are kept, without whitespace.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub struct AcceptNormalizer;
+
+impl VaryNormalizer for AcceptNormalizer {
+    fn header_name(&self) -> &str {
+        "accept"
+    }
+
+    fn normalize(&self, value: Option<&str>) -> Option<String> {
+        by_quality(value?)
+    }
+}
+
+/// Reduces `User-Agent` to a device class: `mobile`, `tablet` or `desktop`.
+///
+/// This is a rough guess from well-known tokens, so use it only if the server varies responses the same way.
+///
+/// ```rust
+/// use http_cache_semantics::{DeviceClassNormalizer, VaryNormalizer};
+///
+/// let n = DeviceClassNormalizer;
+/// assert_eq!(n.normalize(Some("Mozilla/5.0 (iPhone; CPU iPhone OS 17_0 like Mac OS X)")), Some("mobile".into()));
+/// assert_eq!(n.normalize(Some("Mozilla/5.0 (Linux; Android 14; SM-X710)")), Some("tablet".into()));
+/// assert_eq!(n.normalize(Some("curl/8.0")), Some("desktop".into()));
+/// ```
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub struct DeviceClassNormalizer;
+
+impl VaryNormalizer for DeviceClassNormalizer {
+    fn header_name(&self) -> &str {
+        "user-agent"
+    }
+
+    fn normalize(&self, value: Option<&str>) -> Option<String> {
+        let ua = value?;
+        let class = if ua.contains("iPad") || ua.contains("Tablet") || (ua.contains("Android") && !ua.contains("Mobile")) {
+            "tablet"
+        } else if ua.contains("Mobi") || ua.contains("iPhone") || ua.contains("iPod") || ua.contains("Windows Phone") {
+            "mobile"
+        } else {
+            "desktop"
+        };
+        Some(class.into())
+    }
+}
+
+/// One of the built-in normalizers, selected by name. Used where normalizers are configured declaratively, like in `Rule`.
+///
+/// It's serialized in kebab-case, e.g. `"accept-encoding"`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "kebab-case"))]
+#[non_exhaustive]
+pub enum VaryNormalization {
+    /// `AcceptEncodingNormalizer` with the default codings
+    AcceptEncoding,
+    /// `AcceptLanguageNormalizer`
+    AcceptLanguage,
+    /// `AcceptNormalizer`
+    Accept,
+    /// `DeviceClassNormalizer`
+    DeviceClass,
+}
+
+impl VaryNormalizer for VaryNormalization {
+    fn header_name(&self) -> &str {
+        match self {
+            Self::AcceptEncoding => "accept-encoding",
+            Self::AcceptLanguage => "accept-language",
+            Self::Accept => "accept",
+            Self::DeviceClass => "user-agent",
+        }
+    }
+
+    fn normalize(&self, value: Option<&str>) -> Option<String> {
+        match self {
+            Self::AcceptEncoding => AcceptEncodingNormalizer::default().normalize(value),
+            Self::AcceptLanguage => AcceptLanguageNormalizer.normalize(value),
+            Self::Accept => AcceptNormalizer.normalize(value),
+            Self::DeviceClass => DeviceClassNormalizer.normalize(value),
+        }
+    }
+}
+
+/// Lowercase header names listed in `Vary`, in all of its field lines, without duplicates.
+///
+/// `None` if the list includes `*`.
+pub(crate) fn vary_names(res: &HeaderMap) -> Option<Vec<String>> {
+    parse_vary_names(get_all_comma(res.get_all("vary")))
+}
+
+/// Names from comma-separated lists of header names
+pub(crate) fn parse_vary_names<'a>(lists: impl IntoIterator<Item = &'a str>) -> Option<Vec<String>> {
+    let mut names: Vec<String> = Vec::new();
+    for name in lists.into_iter().flat_map(|list| list.split(',')).map(str::trim) {
+        if name == "*" {
+            return None;
+        }
+        if !name.is_empty() && !names.iter().any(|n| n.eq_ignore_ascii_case(name)) {
+            names.push(name.to_ascii_lowercase());
+        }
+    }
+    Some(names)
+}
+
+/// Identifies a variant of a stored response among responses for the same URI,
+/// using values of the request headers listed in `Vary`.
+///
+/// Returned by `CachePolicy::secondary_key()` and `CacheOptions::secondary_key_for_request()`.
+/// It's a 128-bit hash, which is the same across program runs and platforms.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SecondaryKey(u128);
+
+impl SecondaryKey {
+    /// Key of the header values. The names must be sorted.
+    pub(crate) fn new<'a>(values: impl Iterator<Item = (&'a str, Option<&'a [u8]>)>) -> Self {
+        // FNV-1a
+        let mut hash: u128 = 0x6c62272e07bb014262b821756295c58d;
+        let mut write = |bytes: &[u8]| {
+            for &b in bytes {
+                hash ^= u128::from(b);
+                hash = hash.wrapping_mul(0x0000000001000000000000000000013B);
+            }
+        };
+        for (name, value) in values {
+            write(&(name.len() as u64).to_le_bytes());
+            write(name.as_bytes());
+            match value {
+                Some(value) => {
+                    write(&[1]);
+                    write(&(value.len() as u64).to_le_bytes());
+                    write(value);
+                },
+                None => write(&[0]),
+            }
+        }
+        Self(hash)
+    }
+
+    /// The hash as a number
+    #[must_use]
+    pub fn as_u128(&self) -> u128 {
+        self.0
+    }
+}
+
+/// 32 lowercase hex digits
+impl fmt::Display for SecondaryKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:032x}", self.0)
+    }
+}
+
+/// All field lines of the header combined into one value (RFC 9110 section 5.3),
+/// with the whitespace around commas normalized to `, `, and empty list elements removed (RFC 9110 section 5.6.1).
+pub(crate) fn combined_value(headers: &HeaderMap, name: &str) -> Option<Vec<u8>> {
+    let mut lines = headers.get_all(name).iter().peekable();
+    lines.peek()?;
+    let mut out = Vec::new();
+    for item in lines.flat_map(|line| list_items(line.as_bytes())) {
+        let item = item.trim_ascii();
+        if item.is_empty() {
+            continue;
+        }
+        if !out.is_empty() {
+            out.extend_from_slice(b", ");
+        }
+        out.extend_from_slice(item);
+    }
+    Some(out)
+}
+
+/// Splits a field value at commas that aren't inside a quoted string
+fn list_items(value: &[u8]) -> Vec<&[u8]> {
+    let mut items = Vec::new();
+    let (mut start, mut quoted, mut escaped) = (0, false, false);
+    for (i, &b) in value.iter().enumerate() {
+        if escaped {
+            escaped = false;
+        } else if quoted && b == b'\\' {
+            escaped = true;
+        } else if b == b'"' {
+            quoted = !quoted;
+        } else if b == b',' && !quoted {
+            items.push(&value[start..i]);
+            start = i + 1;
+        }
+    }
+    items.push(&value[start..]);
+    items
+}
+
+/// Lowercase items of a comma-separated list with their q-values (RFC 9110 section 12.4.2), without other parameters
+fn weighted_items(value: &str) -> Vec<(String, f32)> {
+    value.split(',').filter_map(|item| {
+        let (item, q) = split_quality(item);
+        (!item.is_empty()).then(|| (item.split(';').next().unwrap_or_default().trim().to_owned(), q))
+    }).collect()
+}
+
+/// The item with whitespace removed around `;`, and its q-value
+fn split_quality(item: &str) -> (String, f32) {
+    let mut q = 1.;
+    let mut out = String::new();
+    for (i, part) in item.split(';').map(str::trim).enumerate() {
+        if i > 0 {
+            if let Some(value) = part.strip_prefix("q=").or_else(|| part.strip_prefix("Q=")) {
+                q = value.trim().parse::<f32>().unwrap_or(1.).clamp(0., 1.);
+                continue;
+            }
+            out.push(';');
+        }
+        out.push_str(part);
+    }
+    (out.to_ascii_lowercase(), q)
+}
+
+/// Acceptable items, the most preferred first. Items with equal q-values keep their order.
+fn by_quality(value: &str) -> Option<String> {
+    let mut items: Vec<_> = value.split(',').map(split_quality).filter(|(item, q)| !item.is_empty() && *q > 0.).collect();
+    if items.is_empty() {
+        return None;
+    }
+    items.sort_by(|(_, a), (_, b)| b.total_cmp(a));
+    let mut out = String::new();
+    for (item, _) in items {
+        if !out.is_empty() {
+            out.push(',');
+        }
+        out.push_str(&item);
+    }
+    Some(out)
+}
@@ -0,0 +1,169 @@
+use http::{header, Request, Response};
+use http_cache_semantics::AfterResponse;
+use http_cache_semantics::CacheOptions;
+use http_cache_semantics::ClockSkew;
+use http_cache_semantics::ClockSkewPolicy;
+use http_cache_semantics::CachePolicy;
+use http_cache_semantics::HttpDate;
+use std::time::Duration;
+use std::time::SystemTime;
+
+fn request_parts(builder: http::request::Builder) -> http::request::Parts {
+    builder.body(()).unwrap().into_parts().0
+}
+
+fn response_parts(builder: http::response::Builder) -> http::response::Parts {
+    builder.body(()).unwrap().into_parts().0
+}
+
+fn secs(s: u64) -> Duration {
+    Duration::from_secs(s)
+}
+
+fn timed_policy(res: http::response::Builder, request_time: SystemTime, response_time: SystemTime) -> CachePolicy {
+    CachePolicy::new_timed(
+        &request_parts(Request::builder()),
+        &response_parts(res),
+        request_time,
+        response_time,
+        CacheOptions::default(),
+    )
+}
+
+#[test]
+fn test_response_delay_adds_to_age() {
+    let request_time = SystemTime::now();
+    let response_time = request_time + secs(5);
+    let res = || Response::builder().header(header::CACHE_CONTROL, "max-age=60").header(header::AGE, "10");
+
+    let policy = timed_policy(res(), request_time, response_time);
+    assert_eq!(policy.age(response_time), secs(15));
+    assert_eq!(policy.age(response_time + secs(1)), secs(16));
+    assert_eq!(policy.time_to_live(response_time), secs(45));
+
+    let untimed = CachePolicy::new_options(&request_parts(Request::builder()), &response_parts(res()), response_time, CacheOptions::default());
+    assert_eq!(untimed.age(response_time), secs(10));
+}
+
+#[test]
+fn test_huge_age_header() {
+    let request_time = SystemTime::now();
+    let response_time = request_time + secs(5);
+    let res = Response::builder().header(header::CACHE_CONTROL, "max-age=60").header(header::AGE, "18446744073709551615");
+
+    let policy = timed_policy(res, request_time, response_time);
+    assert_eq!(policy.age(response_time + secs(1)), Duration::MAX);
+    assert_eq!(policy.time_to_live(response_time), Duration::ZERO);
+}
+
+#[test]
+fn test_apparent_age() {
+    let response_time: SystemTime = HttpDate::parse("Mon, 01 Jan 2024 00:01:00 GMT").unwrap().into();
+    let request_time = response_time - secs(1);
+
+    let res = Response::builder()
+        .header(header::CACHE_CONTROL, "max-age=600")
+        .header(header::DATE, "Mon, 01 Jan 2024 00:00:00 GMT")
+        .header(header::AGE, "5");
+    let policy = timed_policy(res, request_time, response_time);
+    assert_eq!(policy.age(response_time), secs(60));
+}
+
+#[test]
+fn test_date_in_the_future_is_not_negative_age() {
+    let response_time: SystemTime = HttpDate::parse("Mon, 01 Jan 2024 00:00:00 GMT").unwrap().into();
+    let request_time = response_time - secs(2);
+
+    let res = Response::builder()
+        .header(header::CACHE_CONTROL, "max-age=600")
+        .header(header::DATE, "Mon, 01 Jan 2024 00:10:00 GMT");
+    let policy = timed_policy(res, request_time, response_time);
+    assert_eq!(policy.age(response_time), secs(2));
+}
+
+#[test]
+fn test_after_response_timed() {
+    let request_time = SystemTime::now();
+    let policy = timed_policy(
+        Response::builder().header(header::CACHE_CONTROL, "max-age=60").header(header::ETAG, "\"v1\""),
+        request_time,
+        request_time,
+    );
+
+    let later = request_time + secs(100);
+    let req = request_parts(Request::builder().header(header::IF_NONE_MATCH, "\"v1\""));
+    let not_modified = response_parts(Response::builder().status(304).header(header::ETAG, "\"v1\""));
+    let AfterResponse::NotModified(new_policy, _) = policy.after_response_timed(&req, &not_modified, later, later + secs(3)) else {
+        panic!("should be not modified");
+    };
+    assert_eq!(new_policy.age(later + secs(3)), secs(3));
+    assert_eq!(new_policy.time_to_live(later + secs(3)), secs(57));
+}
+
+fn skewed_policy(clock_skew: ClockSkewPolicy, request_time: SystemTime, response_time: SystemTime) -> CachePolicy {
+    CachePolicy::new_timed(
+        &request_parts(Request::builder()),
+        &response_parts(
+            Response::builder()
+                .header(header::DATE, "Mon, 01 Jan 2024 00:00:00 GMT")
+                .header(header::EXPIRES, "Mon, 01 Jan 2024 01:00:00 GMT"),
+        ),
+        request_time,
+        response_time,
+        CacheOptions {
+            clock_skew,
+            ..Default::default()
+        },
+    )
+}
+
+#[test]
+fn test_clock_skew() {
+    let date: SystemTime = HttpDate::parse("Mon, 01 Jan 2024 00:00:00 GMT").unwrap().into();
+
+    let policy = skewed_policy(ClockSkewPolicy::TrustOrigin, date, date + secs(600));
+    assert_eq!(policy.clock_skew(), Some(ClockSkew::Behind(secs(600))));
+    let policy = skewed_policy(ClockSkewPolicy::TrustOrigin, date - secs(600), date - secs(600));
+    assert_eq!(policy.clock_skew(), Some(ClockSkew::Ahead(secs(600))));
+    assert_eq!(policy.clock_skew().unwrap().duration(), secs(600));
+
+    let now = SystemTime::now();
+    let policy = CachePolicy::new_options(&request_parts(Request::builder()), &response_parts(Response::builder()), now, CacheOptions::default());
+    assert_eq!(policy.clock_skew(), None);
+}
+
+#[test]
+fn test_clock_skew_policies() {
+    let date: SystemTime = HttpDate::parse("Mon, 01 Jan 2024 00:00:00 GMT").unwrap().into();
+    // the local clock is 10 minutes ahead of the origin's
+    let response_time = date + secs(600);
+
+    let policy = skewed_policy(ClockSkewPolicy::TrustOrigin, response_time, response_time);
+    assert_eq!(policy.age(response_time), secs(600));
+    assert_eq!(policy.time_to_live(response_time), secs(3000));
+
+    let policy = skewed_policy(ClockSkewPolicy::ExpiresRelative, response_time, response_time);
+    assert_eq!(policy.age(response_time), secs(0));
+    assert_eq!(policy.time_to_live(response_time), secs(3600));
+
+    let policy = skewed_policy(ClockSkewPolicy::Clamp(secs(60)), response_time, response_time);
+    assert_eq!(policy.age(response_time), secs(60));
+    assert_eq!(policy.time_to_live(response_time), secs(3000));
+
+    let policy = skewed_policy(ClockSkewPolicy::Clamp(Duration::ZERO), response_time, response_time);
+    assert_eq!(policy.age(response_time), secs(0));
+    assert_eq!(policy.time_to_live(response_time), secs(3000));
+}
+
+#[test]
+fn test_clock_skew_clamp_unlimited() {
+    let date: SystemTime = HttpDate::parse("Mon, 01 Jan 2024 00:00:00 GMT").unwrap().into();
+    let response_time = date + secs(600);
+    // the origin's clock is behind, and ahead
+    for response_time in [response_time, date - secs(600)] {
+        let policy = skewed_policy(ClockSkewPolicy::Clamp(Duration::MAX), response_time, response_time);
+        let trusted = skewed_policy(ClockSkewPolicy::TrustOrigin, response_time, response_time);
+        assert_eq!(policy.age(response_time), trusted.age(response_time));
+        assert_eq!(policy.time_to_live(response_time), trusted.time_to_live(response_time));
+    }
+}
@@ -0,0 +1,144 @@
+use http::HeaderValue;
+use http_cache_semantics::CacheControl;
+use http_cache_semantics::CacheControlParseError;
+use std::time::Duration;
+
+#[test]
+fn test_parse_typed_directives() {
+    let cc = CacheControl::parse("public, max-age=60, s-maxage=120, stale-while-revalidate=30, immutable").unwrap();
+    assert!(cc.public());
+    assert!(cc.immutable());
+    assert!(!cc.private());
+    assert_eq!(cc.max_age(), Some(Duration::from_secs(60)));
+    assert_eq!(cc.s_maxage(), Some(Duration::from_secs(120)));
+    assert_eq!(cc.stale_while_revalidate(), Some(Duration::from_secs(30)));
+    assert_eq!(cc.stale_if_error(), None);
+}
+
+#[test]
+fn test_quoted_string_with_commas() {
+    let cc = CacheControl::parse(r#"no-cache="set-cookie, x-foo", private"#).unwrap();
+    assert!(cc.no_cache());
+    assert!(cc.private());
+    assert!(cc.private_fields().is_none());
+    assert_eq!(cc.no_cache_fields().unwrap().collect::<Vec<_>>(), ["set-cookie", "x-foo"]);
+    assert_eq!(cc.iter().count(), 2);
+}
+
+#[test]
+fn test_quoted_pair_unescaping() {
+    let cc = CacheControl::parse(r#"ext="a \"b\" \\ c""#).unwrap();
+    assert_eq!(cc.get("ext"), Some(r#"a "b" \ c"#));
+    assert_eq!(cc.to_string(), r#"ext="a \"b\" \\ c""#);
+}
+
+#[test]
+fn test_case_insensitive_names() {
+    let cc = CacheControl::parse("Max-Age=5, NO-STORE").unwrap();
+    assert!(cc.no_store());
+    assert!(cc.contains("no-store"));
+    assert_eq!(cc.max_age(), Some(Duration::from_secs(5)));
+    assert_eq!(cc.to_string(), "max-age=5, no-store");
+}
+
+#[test]
+fn test_max_stale_without_value() {
+    let cc = CacheControl::parse("max-stale").unwrap();
+    assert_eq!(cc.max_stale(), Some(None));
+    let cc = CacheControl::parse("max-stale=10").unwrap();
+    assert_eq!(cc.max_stale(), Some(Some(Duration::from_secs(10))));
+    assert_eq!(CacheControl::new().max_stale(), None);
+}
+
+#[test]
+fn test_delta_seconds_overflow_is_clamped() {
+    let cc = CacheControl::parse("max-age=99999999999999999999999").unwrap();
+    assert_eq!(cc.max_age(), Some(Duration::from_secs(1 << 31)));
+    let cc = CacheControl::parse("max-age=-1").unwrap();
+    assert_eq!(cc.max_age(), None);
+    assert!(cc.contains("max-age"));
+}
+
+#[test]
+fn test_extensions() {
+    let cc = CacheControl::parse("max-age=1, community=\"UCI\", foo").unwrap();
+    assert_eq!(cc.extensions().collect::<Vec<_>>(), [("community", Some("UCI")), ("foo", None)]);
+}
+
+#[test]
+fn test_parse_errors() {
+    assert_eq!(CacheControl::parse("max-age=\"5"), Err(CacheControlParseError::UnterminatedQuotedString(8)));
+    assert_eq!(CacheControl::parse("max-age="), Err(CacheControlParseError::MissingArgument(8)));
+    assert_eq!(CacheControl::parse("max age"), Err(CacheControlParseError::UnexpectedCharacter(4)));
+    assert_eq!(CacheControl::parse("public; private"), Err(CacheControlParseError::UnexpectedCharacter(6)));
+    assert!(CacheControl::parse(",, ,").unwrap().is_empty());
+}
+
+#[test]
+fn test_lossy_parsing_skips_bad_directives() {
+    let values = [
+        HeaderValue::from_static("max age, public"),
+        HeaderValue::from_static("x=\"a, b\" c, max-age=3"),
+    ];
+    assert!(CacheControl::parse_header_values(&values).is_err());
+    let cc = CacheControl::parse_header_values_lossy(&values);
+    assert_eq!(cc.to_string(), "public, max-age=3");
+}
+
+#[test]
+fn test_round_trip() {
+    for s in [
+        "max-age=0, must-revalidate",
+        r#"private="set-cookie", no-cache="x-a, x-b", s-maxage=10"#,
+        r#"foo="", bar="with space", baz=token"#,
+    ] {
+        let cc = CacheControl::parse(s).unwrap();
+        assert_eq!(cc.to_string(), s);
+        assert_eq!(CacheControl::parse(&cc.to_string()).unwrap(), cc);
+    }
+}
+
+#[test]
+fn test_qualified_directives_are_always_quoted() {
+    let mut cc = CacheControl::new();
+    cc.insert("no-cache", Some("set-cookie"));
+    cc.insert("max-age", Some("5"));
+    cc.insert("MAX-AGE", Some("6"));
+    assert_eq!(cc.to_string(), r#"no-cache="set-cookie", max-age=6"#);
+    assert!(cc.remove("no-cache"));
+    assert!(!cc.remove("no-cache"));
+    assert_eq!(cc.to_header_value().unwrap(), "max-age=6");
+}
+
+#[test]
+fn test_conflicting_duplicates() {
+    assert!(!CacheControl::parse("max-age=1, max-age=1").unwrap().has_conflicting_duplicates());
+    assert!(CacheControl::parse("max-age=1, max-age=2").unwrap().has_conflicting_duplicates());
+}
+
+#[test]
+fn test_parse_targeted() {
+    let cc = CacheControl::parse_targeted("max-age=60, private;foo=bar, no-cache=\"x-a\", must-revalidate=?1, no-store=?0").unwrap();
+    assert_eq!(cc.max_age(), Some(Duration::from_secs(60)));
+    assert!(cc.private());
+    assert!(cc.private_fields().is_none());
+    assert_eq!(cc.no_cache_fields().unwrap().collect::<Vec<_>>(), ["x-a"]);
+    assert!(cc.must_revalidate());
+    assert!(!cc.no_store());
+}
+
+#[test]
+fn test_parse_targeted_last_wins() {
+    let cc = CacheControl::parse_targeted("max-age=60, max-age=30, foo=(1 2)").unwrap();
+    assert_eq!(cc.max_age(), Some(Duration::from_secs(30)));
+    assert!(!cc.contains("foo"));
+}
+
+#[test]
+fn test_parse_targeted_is_strict() {
+    assert!(CacheControl::parse_targeted("Max-Age=60").is_err());
+    assert!(CacheControl::parse_targeted("max-age=60,").is_err());
+    assert!(CacheControl::parse_targeted("max-age = 60").is_err());
+    assert!(CacheControl::parse_targeted("no-cache=\"unterminated").is_err());
+    assert!(CacheControl::parse_targeted("").unwrap().is_empty());
+}
@@ -0,0 +1,122 @@
+use http::{header, Method, Request, Response, StatusCode};
+use http_cache_semantics::BeforeRequest;
+use http_cache_semantics::CachePolicy;
+use http_cache_semantics::HttpDate;
+use std::time::{Duration, SystemTime};
+
+fn request_parts(builder: http::request::Builder) -> http::request::Parts {
+    builder.body(()).unwrap().into_parts().0
+}
+
+fn response_parts(builder: http::response::Builder) -> http::response::Parts {
+    builder.body(()).unwrap().into_parts().0
+}
+
+fn policy(res: http::response::Builder) -> CachePolicy {
+    CachePolicy::new(&request_parts(Request::builder().uri("/")), &response_parts(res))
+}
+
+fn fresh_status(policy: &CachePolicy, req: http::request::Builder) -> StatusCode {
+    match policy.before_request(&request_parts(req.uri("/")), SystemTime::now()) {
+        BeforeRequest::Fresh(parts) => parts.status,
+        _ => panic!("expected fresh"),
+    }
+}
+
+fn cacheable() -> http::response::Builder {
+    Response::builder()
+        .header(header::CACHE_CONTROL, "max-age=100")
+        .header(header::CONTENT_TYPE, "text/html")
+        .header(header::CONTENT_LENGTH, "1234")
+}
+
+#[test]
+fn test_if_none_match_weak_comparison() {
+    let policy = policy(cacheable().header(header::ETAG, "W/\"v1\""));
+    assert_eq!(fresh_status(&policy, Request::builder().header(header::IF_NONE_MATCH, "\"v1\"")), StatusCode::NOT_MODIFIED);
+    assert_eq!(fresh_status(&policy, Request::builder().header(header::IF_NONE_MATCH, "\"v0\", W/\"v1\"")), StatusCode::NOT_MODIFIED);
+    assert_eq!(fresh_status(&policy, Request::builder().header(header::IF_NONE_MATCH, "*")), StatusCode::NOT_MODIFIED);
+    assert_eq!(fresh_status(&policy, Request::builder().header(header::IF_NONE_MATCH, "\"v2\"")), StatusCode::OK);
+    assert_eq!(fresh_status(&policy, Request::builder()), StatusCode::OK);
+}
+
+#[test]
+fn test_if_none_match_takes_precedence() {
+    let policy = policy(cacheable()
+        .header(header::ETAG, "\"v1\"")
+        .header(header::LAST_MODIFIED, "Mon, 01 Jan 2024 00:00:00 GMT"));
+    let req = Request::builder()
+        .header(header::IF_NONE_MATCH, "\"v2\"")
+        .header(header::IF_MODIFIED_SINCE, "Tue, 02 Jan 2024 00:00:00 GMT");
+    assert_eq!(fresh_status(&policy, req), StatusCode::OK);
+}
+
+#[test]
+fn test_if_modified_since() {
+    let policy = policy(cacheable().header(header::LAST_MODIFIED, "Mon, 01 Jan 2024 00:00:00 GMT"));
+    assert_eq!(fresh_status(&policy, Request::builder().header(header::IF_MODIFIED_SINCE, "Mon, 01 Jan 2024 00:00:00 GMT")), StatusCode::NOT_MODIFIED);
+    assert_eq!(fresh_status(&policy, Request::builder().header(header::IF_MODIFIED_SINCE, "Sunday, 07-Jan-24 00:00:00 GMT")), StatusCode::NOT_MODIFIED);
+    assert_eq!(fresh_status(&policy, Request::builder().header(header::IF_MODIFIED_SINCE, "Sun, 31 Dec 2023 23:59:59 GMT")), StatusCode::OK);
+    assert_eq!(fresh_status(&policy, Request::builder().header(header::IF_MODIFIED_SINCE, "yesterday")), StatusCode::OK);
+}
+
+#[test]
+fn test_if_modified_since_uses_date() {
+    let date = SystemTime::now() - Duration::from_secs(10);
+    let policy = policy(cacheable().header(header::DATE, HttpDate::from(date).to_string()));
+    let before = HttpDate::from(date - Duration::from_secs(1)).to_string();
+    let after = HttpDate::from(date).to_string();
+    assert_eq!(fresh_status(&policy, Request::builder().header(header::IF_MODIFIED_SINCE, before)), StatusCode::OK);
+    assert_eq!(fresh_status(&policy, Request::builder().header(header::IF_MODIFIED_SINCE, after)), StatusCode::NOT_MODIFIED);
+}
+
+#[test]
+fn test_not_modified_headers() {
+    let policy = policy(cacheable()
+        .header(header::ETAG, "\"v1\"")
+        .header(header::LAST_MODIFIED, "Mon, 01 Jan 2024 00:00:00 GMT")
+        .header(header::VARY, "accept-encoding")
+        .header(header::EXPIRES, "Mon, 01 Jan 2024 00:00:00 GMT")
+        .header("x-custom", "1"));
+    let req = request_parts(Request::builder().uri("/").header(header::IF_NONE_MATCH, "\"v1\""));
+    let BeforeRequest::Fresh(parts) = policy.before_request(&req, SystemTime::now()) else {
+        panic!("expected fresh");
+    };
+    assert_eq!(parts.status, StatusCode::NOT_MODIFIED);
+    let mut names: Vec<_> = parts.headers.keys().map(|h| h.as_str()).collect();
+    names.sort_unstable();
+    assert_eq!(names, ["age", "cache-control", "date", "etag", "expires", "vary"]);
+}
+
+#[test]
+fn test_last_modified_without_etag() {
+    let policy = policy(cacheable().header(header::LAST_MODIFIED, "Mon, 01 Jan 2024 00:00:00 GMT"));
+    let req = request_parts(Request::builder().uri("/").header(header::IF_MODIFIED_SINCE, "Mon, 01 Jan 2024 00:00:00 GMT"));
+    let BeforeRequest::Fresh(parts) = policy.before_request(&req, SystemTime::now()) else {
+        panic!("expected fresh");
+    };
+    assert!(parts.headers.contains_key(header::LAST_MODIFIED));
+    assert!(!parts.headers.contains_key(header::CONTENT_LENGTH));
+}
+
+#[test]
+fn test_no_304_for_other_preconditions() {
+    let policy = policy(cacheable().header(header::ETAG, "\"v1\""));
+    let req = Request::builder().header(header::IF_NONE_MATCH, "\"v1\"").header(header::IF_MATCH, "\"v1\"");
+    assert_eq!(fresh_status(&policy, req), StatusCode::OK);
+
+    let not_found = CachePolicy::new(
+        &request_parts(Request::builder().uri("/")),
+        &response_parts(Response::builder().status(404).header(header::CACHE_CONTROL, "max-age=100").header(header::ETAG, "\"v1\"")),
+    );
+    assert_eq!(fresh_status(&not_found, Request::builder().header(header::IF_NONE_MATCH, "*")), StatusCode::NOT_FOUND);
+}
+
+#[test]
+fn test_head_request() {
+    let req = request_parts(Request::builder().method(Method::HEAD).uri("/"));
+    let res = response_parts(cacheable().header(header::ETAG, "\"v1\""));
+    let policy = CachePolicy::new(&req, &res);
+    let req = Request::builder().method(Method::HEAD).header(header::IF_NONE_MATCH, "\"v1\"");
+    assert_eq!(fresh_status(&policy, req), StatusCode::NOT_MODIFIED);
+}
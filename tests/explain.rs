@@ -0,0 +1,132 @@
+use http::{header, Method, Request, Response};
+use http_cache_semantics::CacheOptions;
+use http_cache_semantics::CachePolicy;
+use http_cache_semantics::CacheabilityRule;
+use http_cache_semantics::FreshnessSource;
+use std::time::Duration;
+use std::time::SystemTime;
+
+fn request_parts(builder: http::request::Builder) -> http::request::Parts {
+    builder.body(()).unwrap().into_parts().0
+}
+
+fn response_parts(builder: http::response::Builder) -> http::response::Parts {
+    builder.body(()).unwrap().into_parts().0
+}
+
+#[test]
+fn test_explain_private_in_shared_cache() {
+    let policy = CachePolicy::new(
+        &request_parts(Request::builder()),
+        &response_parts(Response::builder().header(header::CACHE_CONTROL, "private, max-age=60")),
+    );
+
+    let report = policy.explain();
+    assert!(!report.storable);
+    assert_eq!(report.deciding_rule(), Some(&CacheabilityRule::PrivateInSharedCache));
+    assert_eq!(report.freshness_lifetime, Duration::ZERO);
+    assert_eq!(report.freshness_source, FreshnessSource::MaxAge);
+    assert_eq!(report.to_string(), "not storable: private in shared cache");
+}
+
+#[test]
+fn test_explain_authorization() {
+    let policy = CachePolicy::new(
+        &request_parts(Request::builder().header(header::AUTHORIZATION, "test")),
+        &response_parts(Response::builder().header(header::CACHE_CONTROL, "max-age=60")),
+    );
+
+    let report = policy.explain();
+    assert!(!report.storable);
+    assert_eq!(report.deciding_rule(), Some(&CacheabilityRule::AuthorizationInSharedCache));
+    assert_eq!(report.storability_rules.iter().filter(|r| r.applies).count(), 1);
+}
+
+#[test]
+fn test_explain_status_and_method() {
+    let policy = CachePolicy::new(
+        &request_parts(Request::builder().method(Method::PUT)),
+        &response_parts(Response::builder().status(206).header(header::CACHE_CONTROL, "max-age=60")),
+    );
+
+    let report = policy.explain();
+    assert_eq!(report.deciding_rule(), Some(&CacheabilityRule::MethodNotCacheable(Method::PUT)));
+    let applied: Vec<_> = report.storability_rules.iter().filter(|r| r.applies).map(|r| r.rule.to_string()).collect();
+    assert_eq!(applied, ["method PUT not cacheable", "status 206 not understood"]);
+}
+
+#[test]
+fn test_explain_set_cookie() {
+    let policy = CachePolicy::new(
+        &request_parts(Request::builder()),
+        &response_parts(
+            Response::builder()
+                .header(header::CACHE_CONTROL, "max-age=60")
+                .header(header::SET_COOKIE, "foo=bar"),
+        ),
+    );
+
+    let report = policy.explain();
+    assert!(report.storable);
+    assert_eq!(report.deciding_rule(), Some(&CacheabilityRule::SetCookieInSharedCache));
+    assert_eq!(report.to_string(), "storable\nalways stale: Set-Cookie without public");
+}
+
+#[test]
+fn test_explain_vary_star() {
+    let policy = CachePolicy::new(
+        &request_parts(Request::builder()),
+        &response_parts(Response::builder().header(header::CACHE_CONTROL, "max-age=60").header(header::VARY, "*")),
+    );
+
+    assert_eq!(policy.explain().deciding_rule(), Some(&CacheabilityRule::VaryStar));
+}
+
+#[test]
+fn test_explain_freshness_sources() {
+    let now = SystemTime::now();
+    let explain = |cc: &str, shared| {
+        CachePolicy::new_options(
+            &request_parts(Request::builder()),
+            &response_parts(
+                Response::builder()
+                    .header(header::CACHE_CONTROL, cc)
+                    .header(header::DATE, "Mon, 01 Jan 2024 00:00:00 GMT")
+                    .header(header::LAST_MODIFIED, "Sat, 23 Dec 2023 00:00:00 GMT"),
+            ),
+            now,
+            CacheOptions {
+                shared,
+                ..Default::default()
+            },
+        )
+        .explain()
+    };
+
+    let report = explain("max-age=10, s-maxage=20", true);
+    assert_eq!((report.freshness_source, report.freshness_lifetime.as_secs()), (FreshnessSource::SMaxAge, 20));
+    let report = explain("max-age=10, s-maxage=20", false);
+    assert_eq!((report.freshness_source, report.freshness_lifetime.as_secs()), (FreshnessSource::MaxAge, 10));
+    let report = explain("public", true);
+    assert_eq!((report.freshness_source, report.freshness_lifetime.as_secs()), (FreshnessSource::LastModifiedHeuristic, 9 * 24 * 3600 / 10));
+    assert_eq!(report.to_string(), "storable\nfresh for 77760s from Last-Modified heuristic");
+    let report = explain("immutable", true);
+    assert_eq!((report.freshness_source, report.freshness_lifetime.as_secs()), (FreshnessSource::ImmutableMinimum, 24 * 3600));
+}
+
+#[test]
+fn test_explain_expires() {
+    let policy = CachePolicy::new(
+        &request_parts(Request::builder()),
+        &response_parts(
+            Response::builder()
+                .header(header::DATE, "Mon, 01 Jan 2024 00:00:00 GMT")
+                .header(header::EXPIRES, "Mon, 01 Jan 2024 01:00:00 GMT"),
+        ),
+    );
+
+    let report = policy.explain();
+    assert_eq!(report.freshness_source, FreshnessSource::Expires);
+    assert_eq!(report.freshness_lifetime, Duration::from_secs(3600));
+    assert_eq!(report.deciding_rule(), None);
+}
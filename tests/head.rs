@@ -0,0 +1,102 @@
+use http::{header, Method, Request, Response};
+use http_cache_semantics::BeforeRequest;
+use http_cache_semantics::CachePolicy;
+use http_cache_semantics::CacheabilityRule;
+use http_cache_semantics::StaleReason;
+use std::time::Duration;
+use std::time::SystemTime;
+
+fn request_parts(builder: http::request::Builder) -> http::request::Parts {
+    builder.body(()).unwrap().into_parts().0
+}
+
+fn response_parts(builder: http::response::Builder) -> http::response::Parts {
+    builder.body(()).unwrap().into_parts().0
+}
+
+fn head_request() -> http::request::Parts {
+    request_parts(Request::builder().method(Method::HEAD).uri("/health"))
+}
+
+fn stored(now: SystemTime) -> CachePolicy {
+    let res = Response::builder()
+        .header(header::CACHE_CONTROL, "max-age=60")
+        .header(header::ETAG, "\"v1\"")
+        .header(header::CONTENT_LENGTH, "42")
+        .header("x-version", "1");
+    CachePolicy::new_options(&request_parts(Request::builder().uri("/health")), &response_parts(res), now, Default::default())
+}
+
+#[test]
+fn test_head_response_freshens() {
+    let now = SystemTime::now();
+    let later = now + Duration::from_secs(100);
+    let mut policy = stored(now);
+    assert!(policy.is_stale(later));
+
+    let head = response_parts(Response::builder()
+        .header(header::CACHE_CONTROL, "max-age=300")
+        .header(header::ETAG, "\"v1\"")
+        .header(header::CONTENT_LENGTH, "42")
+        .header("x-version", "2"));
+    assert!(policy.after_head_response(&head_request(), &head, later));
+    assert!(!policy.is_stale(later));
+    assert_eq!(policy.time_to_live(later), Duration::from_secs(300));
+
+    let get = request_parts(Request::builder().uri("/health"));
+    let BeforeRequest::Fresh(parts) = policy.before_request(&get, later) else {
+        panic!("expected fresh");
+    };
+    assert_eq!(parts.headers["x-version"], "2");
+    assert_eq!(parts.headers[header::CONTENT_LENGTH], "42");
+}
+
+#[test]
+fn test_head_response_without_validators() {
+    let now = SystemTime::now();
+    let mut policy = stored(now);
+    let head = response_parts(Response::builder().header(header::CACHE_CONTROL, "max-age=300"));
+    assert!(policy.after_head_response(&head_request(), &head, now));
+    assert_eq!(policy.time_to_live(now), Duration::from_secs(300));
+}
+
+#[test]
+fn test_head_response_mismatch_invalidates() {
+    let now = SystemTime::now();
+    for (etag, len) in [("\"v2\"", "42"), ("\"v1\"", "43")] {
+        let mut policy = stored(now);
+        let head = response_parts(Response::builder()
+            .header(header::CACHE_CONTROL, "max-age=300")
+            .header(header::ETAG, etag)
+            .header(header::CONTENT_LENGTH, len));
+        assert!(!policy.after_head_response(&head_request(), &head, now));
+        assert!(policy.is_stale(now));
+
+        let get = request_parts(Request::builder().uri("/health"));
+        let BeforeRequest::Stale { request, reason, .. } = policy.before_request(&get, now) else {
+            panic!("expected stale");
+        };
+        assert_eq!(reason, StaleReason::NotReusable(CacheabilityRule::HeadResponseMismatch));
+        assert_eq!(request.headers[header::IF_NONE_MATCH], "\"v1\"");
+        assert_eq!(policy.explain().deciding_rule(), Some(&CacheabilityRule::HeadResponseMismatch));
+    }
+}
+
+#[test]
+fn test_head_response_ignored() {
+    let now = SystemTime::now();
+    let mut policy = stored(now);
+    let head = response_parts(Response::builder().header(header::ETAG, "\"v2\""));
+
+    // not a HEAD request, or for another resource
+    let get = request_parts(Request::builder().uri("/health"));
+    assert!(!policy.after_head_response(&get, &head, now));
+    let other = request_parts(Request::builder().method(Method::HEAD).uri("/other"));
+    assert!(!policy.after_head_response(&other, &head, now));
+
+    // not a 200 response
+    let error = response_parts(Response::builder().status(503).header(header::ETAG, "\"v2\""));
+    assert!(!policy.after_head_response(&head_request(), &error, now));
+
+    assert!(!policy.is_stale(now));
+}
@@ -0,0 +1,93 @@
+use http::{header, Request, Response};
+use http_cache_semantics::CacheOptions;
+use http_cache_semantics::CachePolicy;
+use http_cache_semantics::FreshnessSource;
+use http_cache_semantics::HeuristicFreshness;
+use std::time::Duration;
+use std::time::SystemTime;
+
+fn request_parts(builder: http::request::Builder) -> http::request::Parts {
+    builder.body(()).unwrap().into_parts().0
+}
+
+fn response_parts(builder: http::response::Builder) -> http::response::Parts {
+    builder.body(()).unwrap().into_parts().0
+}
+
+const DAY: u64 = 24 * 3600;
+
+fn options() -> CacheOptions {
+    CacheOptions {
+        heuristic_freshness: HeuristicFreshness {
+            max_lifetime: Some(Duration::from_secs(5 * DAY)),
+            status_class_fractions: vec![(4, 0.0078125)],
+            content_type_fractions: vec![("text/html".into(), 0.03125), ("image/*".into(), 0.2)],
+            skip_query: true,
+            default_lifetime: Some(Duration::from_secs(60)),
+        },
+        ..Default::default()
+    }
+}
+
+/// Last modified 100 days before the response
+fn ttl(uri: &str, res: http::response::Builder) -> (Duration, FreshnessSource) {
+    let res = res
+        .header(header::DATE, "Fri, 10 Apr 2020 00:00:00 GMT")
+        .header(header::LAST_MODIFIED, "Thu, 01 Jan 2020 00:00:00 GMT");
+    lifetime(uri, res)
+}
+
+fn lifetime(uri: &str, res: http::response::Builder) -> (Duration, FreshnessSource) {
+    let policy = CachePolicy::new_options(&request_parts(Request::builder().uri(uri)), &response_parts(res), SystemTime::now(), options());
+    let report = policy.explain();
+    (report.freshness_lifetime, report.freshness_source)
+}
+
+#[test]
+fn test_default_is_unlimited() {
+    let res = response_parts(Response::builder()
+        .header(header::DATE, "Fri, 10 Apr 2020 00:00:00 GMT")
+        .header(header::LAST_MODIFIED, "Thu, 01 Jan 2020 00:00:00 GMT"));
+    let policy = CachePolicy::new_options(&request_parts(Request::builder().uri("/?q")), &res, SystemTime::now(), Default::default());
+    assert_eq!(policy.explain().freshness_lifetime, Duration::from_secs(10 * DAY));
+}
+
+#[test]
+fn test_max_lifetime() {
+    assert_eq!(ttl("/", Response::builder()), (Duration::from_secs(5 * DAY), FreshnessSource::LastModifiedHeuristic));
+}
+
+#[test]
+fn test_fractions() {
+    assert_eq!(ttl("/", Response::builder().header(header::CONTENT_TYPE, "text/html; charset=utf-8")).0, Duration::from_secs(100 * DAY / 32));
+    assert_eq!(ttl("/", Response::builder().header(header::CONTENT_TYPE, "Image/PNG")).0, Duration::from_secs(5 * DAY));
+    assert_eq!(ttl("/", Response::builder().status(404)).0, Duration::from_secs(100 * DAY / 128));
+    // content type takes precedence over the status
+    assert_eq!(ttl("/", Response::builder().status(404).header(header::CONTENT_TYPE, "text/html")).0, Duration::from_secs(100 * DAY / 32));
+}
+
+#[test]
+fn test_skip_query() {
+    assert_eq!(ttl("/search?q=1", Response::builder()), (Duration::ZERO, FreshnessSource::None));
+    // explicit freshness isn't affected
+    let res = Response::builder().header(header::CACHE_CONTROL, "max-age=10");
+    assert_eq!(lifetime("/search?q=1", res).0, Duration::from_secs(10));
+}
+
+#[test]
+fn test_default_lifetime() {
+    assert_eq!(lifetime("/", Response::builder()), (Duration::from_secs(60), FreshnessSource::DefaultHeuristic));
+    // not cacheable by default
+    assert_eq!(lifetime("/", Response::builder().status(302)).0, Duration::ZERO);
+    assert_eq!(lifetime("/", Response::builder().status(302).header(header::CACHE_CONTROL, "public")).0, Duration::from_secs(60));
+}
+
+#[test]
+#[cfg(feature = "serde")]
+fn test_deserialize_seconds() {
+    let options: CacheOptions = serde_json::from_str(r#"{"heuristic_freshness": {"max_lifetime": 86400, "default_lifetime": 60}}"#).unwrap();
+    assert_eq!(options.heuristic_freshness.max_lifetime, Some(Duration::from_secs(DAY)));
+    assert_eq!(options.heuristic_freshness.default_lifetime, Some(Duration::from_secs(60)));
+    let json = serde_json::to_value(&options.heuristic_freshness).unwrap();
+    assert_eq!(json["max_lifetime"], 86400);
+}
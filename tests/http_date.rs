@@ -0,0 +1,94 @@
+use http::{header, Request, Response};
+use http_cache_semantics::CachePolicy;
+use http_cache_semantics::HttpDate;
+use std::time::Duration;
+use std::time::SystemTime;
+
+fn request_parts(builder: http::request::Builder) -> http::request::Parts {
+    builder.body(()).unwrap().into_parts().0
+}
+
+fn response_parts(builder: http::response::Builder) -> http::response::Parts {
+    builder.body(()).unwrap().into_parts().0
+}
+
+const NOV_6_1994: i64 = 784_111_777;
+
+#[test]
+fn test_parse_all_formats() {
+    for s in [
+        "Sun, 06 Nov 1994 08:49:37 GMT",
+        "Sunday, 06-Nov-94 08:49:37 GMT",
+        "Sun Nov  6 08:49:37 1994",
+        " Sun, 06 Nov 1994 08:49:37 GMT ",
+        "Sun, 06 Nov 1994 08:49:37 +0000",
+    ] {
+        assert_eq!(HttpDate::parse(s).unwrap().unix_timestamp(), NOV_6_1994, "{s}");
+    }
+}
+
+#[test]
+fn test_format_imf_fixdate() {
+    let date = HttpDate::from_unix_timestamp(NOV_6_1994).unwrap();
+    assert_eq!(date.to_string(), "Sun, 06 Nov 1994 08:49:37 GMT");
+    assert_eq!(HttpDate::from(SystemTime::UNIX_EPOCH + Duration::from_millis(1500)).to_string(), "Thu, 01 Jan 1970 00:00:01 GMT");
+    assert_eq!(SystemTime::from(date), SystemTime::UNIX_EPOCH + Duration::from_secs(NOV_6_1994 as u64));
+}
+
+#[test]
+fn test_rfc850_two_digit_year() {
+    let now = HttpDate::parse("Fri, 01 Jan 2027 00:00:00 GMT").unwrap().into();
+    let year = |s| HttpDate::parse_relative_to(s, now).unwrap().to_string();
+    assert_eq!(year("Saturday, 01-Jan-77 00:00:00 GMT"), "Fri, 01 Jan 2077 00:00:00 GMT");
+    assert_eq!(year("Wednesday, 01-Jan-78 00:00:00 GMT"), "Sun, 01 Jan 1978 00:00:00 GMT");
+    assert_eq!(year("Sunday, 01-Jan-00 00:00:00 GMT"), "Sat, 01 Jan 2000 00:00:00 GMT");
+}
+
+#[test]
+fn test_invalid_dates() {
+    for s in [
+        "0",
+        "",
+        "Sun, 31 Nov 1994 08:49:37 GMT",
+        "Sun, 06 Nov 1994 25:49:37 GMT",
+        "Sun, 06 Nov 1994 08:49:37 GMT trailing",
+        "Sun Nov 6 08:49:37 1994",
+        "Sunday, 06-Nov-1994 08:49:37 GMT",
+    ] {
+        assert!(HttpDate::parse(s).is_err(), "{s}");
+    }
+}
+
+#[test]
+fn test_legacy_expires_formats() {
+    let now = HttpDate::parse("Sun, 06 Nov 1994 08:49:37 GMT").unwrap().into();
+    for expires in ["Sunday, 06-Nov-94 09:49:37 GMT", "Sun Nov  6 09:49:37 1994"] {
+        let policy = CachePolicy::new_options(
+            &request_parts(Request::builder()),
+            &response_parts(
+                Response::builder()
+                    .header(header::DATE, "Sun Nov  6 08:49:37 1994")
+                    .header(header::EXPIRES, expires),
+            ),
+            now,
+            Default::default(),
+        );
+        assert_eq!(policy.time_to_live(now), Duration::from_secs(3600), "{expires}");
+    }
+}
+
+#[test]
+fn test_cached_response_date_format() {
+    let now = HttpDate::parse("Sun, 06 Nov 1994 08:49:37 GMT").unwrap().into();
+    let req = request_parts(Request::builder());
+    let policy = CachePolicy::new_options(
+        &req,
+        &response_parts(Response::builder().header(header::CACHE_CONTROL, "max-age=60")),
+        now,
+        Default::default(),
+    );
+    let http_cache_semantics::BeforeRequest::Fresh(res) = policy.before_request(&req, now) else {
+        panic!("should be fresh");
+    };
+    assert_eq!(res.headers[header::DATE], "Sun, 06 Nov 1994 08:49:37 GMT");
+}
@@ -0,0 +1,85 @@
+use http::{header, Method, Request, Response};
+use http_cache_semantics::invalidation_targets;
+use http_cache_semantics::CachePolicy;
+
+fn request_parts(builder: http::request::Builder) -> http::request::Parts {
+    builder.body(()).unwrap().into_parts().0
+}
+
+fn response_parts(builder: http::response::Builder) -> http::response::Parts {
+    builder.body(()).unwrap().into_parts().0
+}
+
+fn targets(method: Method, uri: &str, res: http::response::Builder) -> Vec<String> {
+    let req = request_parts(Request::builder().method(method).uri(uri).header(header::HOST, "example.com"));
+    invalidation_targets(&req, &response_parts(res)).iter().map(|u| u.to_string()).collect()
+}
+
+#[test]
+fn test_unsafe_methods_invalidate_target_uri() {
+    for method in [Method::POST, Method::PUT, Method::DELETE, Method::PATCH, Method::from_bytes(b"PURGE").unwrap()] {
+        assert_eq!(targets(method, "https://example.com/a?b", Response::builder()), ["https://example.com/a?b"]);
+    }
+    for method in [Method::GET, Method::HEAD, Method::OPTIONS, Method::TRACE] {
+        assert!(targets(method, "https://example.com/a", Response::builder()).is_empty());
+    }
+}
+
+#[test]
+fn test_error_responses_dont_invalidate() {
+    assert!(targets(Method::POST, "/a", Response::builder().status(404)).is_empty());
+    assert!(targets(Method::POST, "/a", Response::builder().status(503)).is_empty());
+    assert!(!targets(Method::POST, "/a", Response::builder().status(303)).is_empty());
+}
+
+#[test]
+fn test_location_headers_are_resolved() {
+    let res = || {
+        Response::builder()
+            .status(201)
+            .header(header::LOCATION, "../items/42#top")
+            .header(header::CONTENT_LOCATION, "?page=2")
+    };
+    assert_eq!(targets(Method::POST, "https://example.com/api/new", res()), [
+        "https://example.com/api/new",
+        "https://example.com/items/42",
+        "https://example.com/api/new?page=2",
+    ]);
+    assert_eq!(targets(Method::POST, "/api/new", res()), ["/api/new", "/items/42", "/api/new?page=2"]);
+
+    let res = Response::builder().header(header::LOCATION, "/a/./b/../c").header(header::CONTENT_LOCATION, "/api/new");
+    assert_eq!(targets(Method::POST, "/api/new", res), ["/api/new", "/a/c"]);
+}
+
+#[test]
+fn test_cross_origin_locations_are_ignored() {
+    let res = Response::builder()
+        .header(header::LOCATION, "https://other.example.com/x")
+        .header(header::CONTENT_LOCATION, "//example.com:443/y");
+    assert_eq!(targets(Method::PUT, "https://example.com/a", res), ["https://example.com/a", "https://example.com:443/y"]);
+
+    let res = Response::builder().header(header::LOCATION, "http://example.com/x");
+    assert_eq!(targets(Method::PUT, "https://example.com/a", res), ["https://example.com/a"]);
+
+    let res = Response::builder().header(header::LOCATION, "https://EXAMPLE.com/x");
+    assert_eq!(targets(Method::PUT, "/a", res), ["/a", "/x"]);
+}
+
+#[test]
+fn test_is_invalidated_by() {
+    let stored = |uri: &str| {
+        CachePolicy::new(
+            &request_parts(Request::builder().uri(uri).header(header::HOST, "example.com")),
+            &response_parts(Response::builder().header(header::CACHE_CONTROL, "max-age=60")),
+        )
+    };
+    let post = request_parts(Request::builder().method(Method::POST).uri("/items/").header(header::HOST, "example.com"));
+    let created = response_parts(Response::builder().status(201).header(header::LOCATION, "https://example.com/items/42"));
+
+    assert!(stored("/items/").is_invalidated_by(&post, &created));
+    assert!(stored("/items/42").is_invalidated_by(&post, &created));
+    assert!(stored("https://example.com/items/42").is_invalidated_by(&post, &created));
+    assert!(!stored("/items/43").is_invalidated_by(&post, &created));
+    assert!(!stored("https://other.example.com/items/").is_invalidated_by(&post, &created));
+    assert!(!stored("/items/").is_invalidated_by(&post, &response_parts(Response::builder().status(500))));
+}
@@ -0,0 +1,165 @@
+use http::{header, Method, Request, Response, StatusCode};
+use http_cache_semantics::BeforeRequest;
+use http_cache_semantics::CacheOptions;
+use http_cache_semantics::CachePolicy;
+use http_cache_semantics::CacheabilityRule;
+use http_cache_semantics::FreshnessSource;
+use http_cache_semantics::Override;
+use http_cache_semantics::OverrideContext;
+use http_cache_semantics::PolicyOverride;
+use std::sync::Arc;
+use std::time::Duration;
+use std::time::SystemTime;
+
+fn request_parts(builder: http::request::Builder) -> http::request::Parts {
+    builder.body(()).unwrap().into_parts().0
+}
+
+fn response_parts(builder: http::response::Builder) -> http::response::Parts {
+    builder.body(()).unwrap().into_parts().0
+}
+
+/// Applies `result` to URIs under `prefix`
+#[derive(Debug)]
+struct ForPrefix {
+    prefix: &'static str,
+    result: Override,
+}
+
+impl PolicyOverride for ForPrefix {
+    fn evaluate(&self, ctx: &OverrideContext<'_>) -> Option<Override> {
+        ctx.uri.path().starts_with(self.prefix).then(|| self.result.clone())
+    }
+}
+
+fn policy(uri: &str, res: http::response::Builder, result: Override) -> CachePolicy {
+    let options = CacheOptions {
+        policy_overrides: vec![Arc::new(ForPrefix { prefix: "/api/", result })],
+        ..Default::default()
+    };
+    CachePolicy::new_options(&request_parts(Request::builder().uri(uri)), &response_parts(res), SystemTime::now(), options)
+}
+
+#[test]
+fn test_force_ttl() {
+    let ttl = Override { ttl: Some(Duration::from_secs(30)), ..Override::new("api-ttl") };
+    let policy = policy("/api/items", Response::builder(), ttl.clone());
+    let report = policy.explain();
+    assert_eq!(report.freshness_lifetime, Duration::from_secs(30));
+    assert_eq!(report.freshness_source, FreshnessSource::Override);
+    assert_eq!(report.applied_override, Some(ttl));
+    assert!(report.to_string().contains("override: api-ttl"));
+
+    let req = request_parts(Request::builder().uri("/api/items"));
+    let BeforeRequest::Fresh(parts) = policy.before_request(&req, SystemTime::now()) else {
+        panic!("expected fresh");
+    };
+    assert_eq!(parts.headers["cache-override"], "api-ttl");
+}
+
+#[test]
+fn test_not_applied() {
+    let ttl = Override { ttl: Some(Duration::from_secs(30)), ..Override::new("api-ttl") };
+    let policy = policy("/static/app.js", Response::builder().header(header::CACHE_CONTROL, "max-age=60"), ttl);
+    let report = policy.explain();
+    assert_eq!(report.freshness_lifetime, Duration::from_secs(60));
+    assert_eq!(report.freshness_source, FreshnessSource::MaxAge);
+    assert_eq!(report.applied_override, None);
+
+    let req = request_parts(Request::builder().uri("/static/app.js"));
+    let BeforeRequest::Fresh(parts) = policy.before_request(&req, SystemTime::now()) else {
+        panic!("expected fresh");
+    };
+    assert!(!parts.headers.contains_key("cache-override"));
+}
+
+#[test]
+fn test_ignore_no_store() {
+    let store = Override { storable: Some(true), ttl: Some(Duration::from_secs(10)), ..Override::new("store-anyway") };
+    let policy = policy("/api/items", Response::builder().header(header::CACHE_CONTROL, "no-store"), store);
+    assert!(policy.is_storable());
+
+    let report = policy.explain();
+    assert!(report.storable);
+    assert_eq!(report.freshness_lifetime, Duration::from_secs(10));
+    assert_eq!(report.deciding_rule(), None);
+    assert!(report.storability_rules.iter().all(|r| !r.applies));
+}
+
+#[test]
+fn test_forced_storable_keeps_request_rules() {
+    let store = Override { storable: Some(true), ttl: Some(Duration::from_secs(10)), ..Override::new("store-anyway") };
+    let options = CacheOptions {
+        policy_overrides: vec![Arc::new(ForPrefix { prefix: "/api/", result: store })],
+        ..Default::default()
+    };
+    let now = SystemTime::now();
+    let res = response_parts(Response::builder().header(header::CACHE_CONTROL, "no-store"));
+
+    let put = request_parts(Request::builder().method(Method::PUT).uri("/api/items"));
+    let policy = CachePolicy::new_options(&put, &res, now, options.clone());
+    assert!(!policy.is_storable());
+    assert_eq!(policy.explain().deciding_rule(), Some(&CacheabilityRule::MethodNotCacheable(Method::PUT)));
+
+    let no_store = request_parts(Request::builder().uri("/api/items").header(header::CACHE_CONTROL, "no-store"));
+    assert!(!CachePolicy::new_options(&no_store, &res, now, options.clone()).is_storable());
+
+    let authorized = request_parts(Request::builder().uri("/api/items").header(header::AUTHORIZATION, "Bearer 1"));
+    assert!(!CachePolicy::new_options(&authorized, &res, now, options.clone()).is_storable());
+
+    // without `partial_content` a 206 isn't understood, so it's not stored nor served as the complete response
+    let get = request_parts(Request::builder().uri("/api/items"));
+    let partial = response_parts(Response::builder().status(206).header(header::CONTENT_RANGE, "bytes 0-9/100"));
+    let policy = CachePolicy::new_options(&get, &partial, now, options);
+    assert!(!policy.is_storable());
+    assert_eq!(policy.explain().deciding_rule(), Some(&CacheabilityRule::StatusNotUnderstood(StatusCode::PARTIAL_CONTENT)));
+    assert!(!policy.before_request(&get, now).satisfies_without_revalidation());
+}
+
+#[test]
+fn test_never_store() {
+    let skip = Override { storable: Some(false), ..Override::new("no-api-cache") };
+    let policy = policy("/api/items", Response::builder().header(header::CACHE_CONTROL, "max-age=60"), skip);
+    assert!(!policy.is_storable());
+    assert_eq!(policy.time_to_live(SystemTime::now()), Duration::ZERO);
+    assert_eq!(policy.explain().deciding_rule(), Some(&CacheabilityRule::Override("no-api-cache".into())));
+}
+
+#[test]
+fn test_cap_max_age() {
+    let cap = Override { max_ttl: Some(Duration::from_secs(300)), ..Override::new("cap") };
+    let long = policy("/api/items", Response::builder().header(header::CACHE_CONTROL, "max-age=86400"), cap.clone());
+    assert_eq!(long.explain().freshness_lifetime, Duration::from_secs(300));
+    assert_eq!(long.explain().freshness_source, FreshnessSource::Override);
+
+    let short = policy("/api/items", Response::builder().header(header::CACHE_CONTROL, "max-age=60"), cap);
+    assert_eq!(short.explain().freshness_lifetime, Duration::from_secs(60));
+    assert_eq!(short.explain().freshness_source, FreshnessSource::MaxAge);
+}
+
+#[test]
+fn test_reuse_rules_still_apply() {
+    let ttl = Override { ttl: Some(Duration::from_secs(30)), ..Override::new("api-ttl") };
+    let policy = policy("/api/items", Response::builder().header(header::CACHE_CONTROL, "no-cache"), ttl);
+    assert!(policy.is_storable());
+    assert_eq!(policy.time_to_live(SystemTime::now()), Duration::ZERO);
+    assert_eq!(policy.explain().deciding_rule(), Some(&CacheabilityRule::NoCache));
+}
+
+#[test]
+#[cfg(feature = "serde")]
+fn test_survives_serialization() {
+    let ttl = Override { ttl: Some(Duration::from_secs(30)), ..Override::new("api-ttl") };
+    let policy = policy("/api/items", Response::builder(), ttl.clone());
+    let json = serde_json::to_string(&policy).unwrap();
+    let policy: CachePolicy = serde_json::from_str(&json).unwrap();
+    assert_eq!(policy.explain().applied_override, Some(ttl));
+    assert_eq!(policy.explain().freshness_lifetime, Duration::from_secs(30));
+}
+
+#[test]
+fn test_unwind_safe() {
+    fn assert_unwind_safe<T: std::panic::UnwindSafe + std::panic::RefUnwindSafe>() {}
+    assert_unwind_safe::<CacheOptions>();
+    assert_unwind_safe::<CachePolicy>();
+}
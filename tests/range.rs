@@ -0,0 +1,272 @@
+use http::{header, Request, Response, StatusCode};
+use http_cache_semantics::AfterResponse;
+use http_cache_semantics::BeforeRequest;
+use http_cache_semantics::CacheOptions;
+use http_cache_semantics::CachePolicy;
+use http_cache_semantics::ContentRange;
+use http_cache_semantics::StaleReason;
+use std::time::Duration;
+use std::time::SystemTime;
+
+fn request_parts(builder: http::request::Builder) -> http::request::Parts {
+    builder.body(()).unwrap().into_parts().0
+}
+
+fn response_parts(builder: http::response::Builder) -> http::response::Parts {
+    builder.body(()).unwrap().into_parts().0
+}
+
+fn partial_options() -> CacheOptions {
+    CacheOptions {
+        partial_content: true,
+        ..Default::default()
+    }
+}
+
+fn policy(res: http::response::Builder, now: SystemTime) -> CachePolicy {
+    CachePolicy::new_options(&request_parts(Request::builder().uri("/video")), &response_parts(res), now, partial_options())
+}
+
+fn complete(etag: &str) -> http::response::Builder {
+    Response::builder()
+        .header(header::CACHE_CONTROL, "max-age=60")
+        .header(header::ETAG, etag)
+        .header(header::CONTENT_LENGTH, "1000")
+}
+
+fn partial(content_range: &str, etag: &str) -> http::response::Builder {
+    Response::builder()
+        .status(206)
+        .header(header::CACHE_CONTROL, "max-age=60")
+        .header(header::ETAG, etag)
+        .header(header::CONTENT_RANGE, content_range)
+}
+
+fn range_request(range: &str) -> http::request::Builder {
+    Request::builder().uri("/video").header(header::RANGE, range)
+}
+
+fn fresh(policy: &CachePolicy, req: http::request::Builder, now: SystemTime) -> http::response::Parts {
+    match policy.before_request(&request_parts(req), now) {
+        BeforeRequest::Fresh(res) => res,
+        _ => panic!("should be fresh"),
+    }
+}
+
+#[test]
+fn test_content_range_parsing() {
+    assert_eq!(ContentRange::parse("bytes 0-499/1234"), Some(ContentRange { range: 0..=499, complete_length: Some(1234) }));
+    assert_eq!(ContentRange::parse("bytes 0-1234/1234"), None);
+    assert_eq!(ContentRange::parse("bytes 5-4/*"), None);
+    assert_eq!(ContentRange::parse("bytes */1234"), None);
+    assert_eq!(ContentRange::parse("items 0-4/10"), None);
+}
+
+#[test]
+fn test_partial_responses_are_storable() {
+    let now = SystemTime::now();
+    let policy = policy(partial("bytes 0-499/1000", "\"a\""), now);
+    assert!(policy.is_storable());
+    assert_eq!(policy.stored_ranges(), Some(&[0..=499][..]));
+
+    let multipart = Response::builder()
+        .status(206)
+        .header(header::CACHE_CONTROL, "max-age=60")
+        .header(header::CONTENT_TYPE, "multipart/byteranges; boundary=x");
+    assert!(!self::policy(multipart, now).is_storable());
+
+    let res = response_parts(partial("bytes 0-499/1000", "\"a\""));
+    assert!(!CachePolicy::new_options(&request_parts(Request::builder()), &res, now, CacheOptions::default()).is_storable());
+}
+
+#[test]
+fn test_range_from_complete_response() {
+    let now = SystemTime::now();
+    let policy = policy(complete("\"a\""), now);
+    assert_eq!(policy.stored_ranges(), None);
+
+    let res = fresh(&policy, range_request("bytes=100-199"), now);
+    assert_eq!(res.status, StatusCode::PARTIAL_CONTENT);
+    assert_eq!(res.headers[header::CONTENT_RANGE], "bytes 100-199/1000");
+    assert_eq!(res.headers[header::CONTENT_LENGTH], "100");
+
+    let res = fresh(&policy, range_request("bytes=-100"), now);
+    assert_eq!(res.headers[header::CONTENT_RANGE], "bytes 900-999/1000");
+    let res = fresh(&policy, range_request("bytes=900-5000"), now);
+    assert_eq!(res.headers[header::CONTENT_RANGE], "bytes 900-999/1000");
+
+    // multiple ranges and unsatisfiable ranges are ignored
+    for range in ["bytes=0-1, 5-6", "bytes=1000-", "pages=1-2"] {
+        let res = fresh(&policy, range_request(range), now);
+        assert_eq!(res.status, StatusCode::OK, "{range}");
+        assert_eq!(res.headers[header::CONTENT_LENGTH], "1000");
+    }
+}
+
+#[test]
+fn test_if_range() {
+    let now = SystemTime::now();
+    let policy = policy(complete("\"a\""), now);
+
+    let res = fresh(&policy, range_request("bytes=0-9").header(header::IF_RANGE, "\"a\""), now);
+    assert_eq!(res.status, StatusCode::PARTIAL_CONTENT);
+    let res = fresh(&policy, range_request("bytes=0-9").header(header::IF_RANGE, "\"b\""), now);
+    assert_eq!(res.status, StatusCode::OK);
+
+    let weak = self::policy(complete("W/\"a\""), now);
+    let res = fresh(&weak, range_request("bytes=0-9").header(header::IF_RANGE, "W/\"a\""), now);
+    assert_eq!(res.status, StatusCode::OK);
+
+    let dated = self::policy(
+        Response::builder()
+            .header(header::CACHE_CONTROL, "max-age=60")
+            .header(header::CONTENT_LENGTH, "1000")
+            .header(header::DATE, "Wed, 03 Jan 2024 00:00:00 GMT")
+            .header(header::LAST_MODIFIED, "Mon, 01 Jan 2024 00:00:00 GMT"),
+        now,
+    );
+    let res = fresh(&dated, range_request("bytes=0-9").header(header::IF_RANGE, "Mon, 01 Jan 2024 00:00:00 GMT"), now);
+    assert_eq!(res.status, StatusCode::PARTIAL_CONTENT);
+}
+
+#[test]
+fn test_range_from_partial_response() {
+    let now = SystemTime::now();
+    let policy = policy(partial("bytes 0-499/1000", "\"a\""), now);
+
+    let res = fresh(&policy, range_request("bytes=100-199"), now);
+    assert_eq!(res.headers[header::CONTENT_RANGE], "bytes 100-199/1000");
+
+    for req in [range_request("bytes=400-600"), Request::builder().uri("/video")] {
+        match policy.before_request(&request_parts(req), now) {
+            BeforeRequest::Stale { request, matches, reason } => {
+                assert!(matches);
+                assert_eq!(reason, StaleReason::RangeNotStored);
+                assert_eq!(reason.cache_status_fwd(), "partial");
+                assert!(!request.headers.contains_key(header::IF_NONE_MATCH));
+            },
+            _ => panic!("should be stale"),
+        }
+    }
+}
+
+#[test]
+fn test_combining_ranges() {
+    let now = SystemTime::now();
+    let policy = policy(partial("bytes 0-99/1000", "\"a\""), now);
+    let req = request_parts(range_request("bytes=200-299"));
+
+    let AfterResponse::Combined(policy, res) = policy.after_response(&req, &response_parts(partial("bytes 200-299/1000", "\"a\"")), now) else {
+        panic!("should be combined");
+    };
+    assert_eq!(res.status, StatusCode::PARTIAL_CONTENT);
+    assert_eq!(res.headers[header::CONTENT_RANGE], "bytes 200-299/1000");
+    assert_eq!(policy.stored_ranges(), Some(&[0..=99, 200..=299][..]));
+
+    let req = request_parts(range_request("bytes=100-999"));
+    let AfterResponse::Combined(policy, _) = policy.after_response(&req, &response_parts(partial("bytes 100-999/1000", "\"a\"")), now) else {
+        panic!("should be combined");
+    };
+    assert_eq!(policy.stored_ranges(), None);
+    let res = fresh(&policy, Request::builder().uri("/video"), now);
+    assert_eq!(res.status, StatusCode::OK);
+    assert_eq!(res.headers[header::CONTENT_LENGTH], "1000");
+    assert!(!res.headers.contains_key(header::CONTENT_RANGE));
+}
+
+#[test]
+fn test_different_validators_are_not_combined() {
+    let now = SystemTime::now();
+    let policy = policy(partial("bytes 0-99/1000", "\"a\""), now);
+    let req = request_parts(range_request("bytes=100-199"));
+
+    for etag in ["\"b\"", "W/\"a\""] {
+        let res = response_parts(partial("bytes 100-199/1000", etag));
+        let AfterResponse::Modified(new_policy, _) = policy.after_response(&req, &res, now) else {
+            panic!("should replace the stored response");
+        };
+        assert_ne!(new_policy.stored_ranges(), Some(&[0..=199][..]));
+    }
+}
+
+#[test]
+fn test_incomplete_response_completion() {
+    let now = SystemTime::now();
+    let mut policy = CachePolicy::new_options(
+        &request_parts(Request::builder().uri("/video")),
+        &response_parts(complete("\"a\"")),
+        now,
+        CacheOptions::default(),
+    );
+    policy.mark_incomplete(300);
+    assert_eq!(policy.stored_ranges(), Some(&[0..=299][..]));
+
+    let res = fresh(&policy, range_request("bytes=0-99"), now);
+    assert_eq!(res.headers[header::CONTENT_RANGE], "bytes 0-99/1000");
+
+    let BeforeRequest::Stale { request, reason, .. } = policy.before_request(&request_parts(Request::builder().uri("/video")), now) else {
+        panic!("incomplete response can't be used");
+    };
+    assert_eq!(reason, StaleReason::RangeNotStored);
+    assert_eq!(request.headers[header::RANGE], "bytes=300-999");
+    assert_eq!(request.headers[header::IF_RANGE], "\"a\"");
+    assert!(!request.headers.contains_key(header::IF_NONE_MATCH));
+
+    let AfterResponse::Combined(policy, _) = policy.after_response(&request, &response_parts(partial("bytes 300-999/1000", "\"a\"")), now) else {
+        panic!("should be combined");
+    };
+    assert_eq!(policy.stored_ranges(), None);
+    let res = fresh(&policy, Request::builder().uri("/video"), now);
+    assert_eq!(res.status, StatusCode::OK);
+}
+
+#[test]
+fn test_incomplete_response_only_if_cached() {
+    let now = SystemTime::now();
+    let mut policy = policy(complete("\"a\"").header(header::CACHE_CONTROL, "stale-while-revalidate=60"), now);
+    policy.mark_incomplete(300);
+
+    let req = |range: &str| request_parts(range_request(range).header(header::CACHE_CONTROL, "only-if-cached"));
+    assert!(matches!(policy.before_request(&req("bytes=0-99"), now), BeforeRequest::Fresh(_)));
+    assert!(matches!(policy.before_request(&req("bytes=200-399"), now), BeforeRequest::GatewayTimeout(_)));
+    let later = now + Duration::from_secs(90);
+    assert!(matches!(policy.before_request(&req("bytes=0-99"), later), BeforeRequest::StaleWhileRevalidate { .. }));
+    assert!(matches!(policy.before_request(&req("bytes=200-399"), later), BeforeRequest::GatewayTimeout(_)));
+}
+
+#[test]
+fn test_incomplete_response_range_request() {
+    let now = SystemTime::now();
+    let mut policy = policy(partial("bytes 0-499/1000", "\"a\""), now);
+    policy.mark_incomplete(200);
+    assert_eq!(policy.stored_ranges(), Some(&[0..=199][..]));
+
+    let BeforeRequest::Stale { request, .. } = policy.before_request(&request_parts(range_request("bytes=100-399")), now) else {
+        panic!("should be stale");
+    };
+    assert_eq!(request.headers[header::RANGE], "bytes=200-399");
+
+    let BeforeRequest::Stale { request, .. } = policy.before_request(&request_parts(range_request("bytes=600-699")), now) else {
+        panic!("should be stale");
+    };
+    assert_eq!(request.headers[header::RANGE], "bytes=600-699");
+    assert!(!request.headers.contains_key(header::IF_RANGE));
+}
+
+#[test]
+fn test_incomplete_response_without_strong_validator() {
+    let now = SystemTime::now();
+    let mut policy = policy(complete("W/\"a\""), now);
+    policy.mark_incomplete(0);
+    assert_eq!(policy.stored_ranges(), Some(&[][..]));
+
+    let BeforeRequest::Stale { request, .. } = policy.before_request(&request_parts(Request::builder().uri("/video")), now) else {
+        panic!("should be stale");
+    };
+    assert!(!request.headers.contains_key(header::RANGE));
+    assert!(!request.headers.contains_key(header::IF_RANGE));
+
+    let mut policy = self::policy(complete("\"a\""), now);
+    policy.mark_incomplete(1000);
+    assert_eq!(policy.stored_ranges(), None);
+}
@@ -1,3 +1,5 @@
+#![allow(clippy::bool_assert_comparison)]
+
 use http::{header, Method, Request, Response};
 use http_cache_semantics::CacheOptions;
 use http_cache_semantics::CachePolicy;
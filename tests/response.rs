@@ -602,3 +602,128 @@ fn get_cached_response(
         _ => panic!("stale"),
     }
 }
+
+#[test]
+fn test_must_understand_overrides_no_store() {
+    let policy = CachePolicy::new(
+        &request_parts(Request::builder().method(Method::GET)),
+        &response_parts(
+            Response::builder().header(header::CACHE_CONTROL, "max-age=100, must-understand, no-store"),
+        ),
+    );
+
+    assert!(policy.is_storable());
+    assert!(!policy.is_stale(SystemTime::now()));
+}
+
+#[test]
+fn test_must_understand_unknown_status() {
+    let policy = CachePolicy::new(
+        &request_parts(Request::builder().method(Method::GET)),
+        &response_parts(
+            Response::builder()
+                .status(206)
+                .header(header::CACHE_CONTROL, "max-age=100, must-understand, no-store"),
+        ),
+    );
+
+    assert!(!policy.is_storable());
+}
+
+#[test]
+fn test_configurable_understood_statuses() {
+    let now = SystemTime::now();
+    let request = request_parts(Request::builder().method(Method::GET));
+    let response = response_parts(
+        Response::builder()
+            .status(402)
+            .header(header::CACHE_CONTROL, "max-age=100, must-understand, no-store"),
+    );
+    let mut understood_statuses = http_cache_semantics::UNDERSTOOD_STATUSES.to_vec();
+    understood_statuses.push(402);
+    let policy = CachePolicy::new_options(&request, &response, now, CacheOptions {
+        understood_statuses,
+        ..Default::default()
+    });
+    assert!(policy.is_storable());
+
+    let policy = CachePolicy::new_options(&request, &response_parts(Response::builder().header(header::CACHE_CONTROL, "max-age=100")), now, CacheOptions {
+        understood_statuses: vec![404],
+        ..Default::default()
+    });
+    assert!(!policy.is_storable());
+}
+
+#[test]
+fn test_partial_content_requires_range_tracking() {
+    let now = SystemTime::now();
+    let request = request_parts(Request::builder().method(Method::GET));
+    let response = response_parts(
+        Response::builder()
+            .status(206)
+            .header(header::CACHE_CONTROL, "max-age=100")
+            .header(header::CONTENT_RANGE, "bytes 0-9/100"),
+    );
+    let mut understood_statuses = http_cache_semantics::UNDERSTOOD_STATUSES.to_vec();
+    understood_statuses.push(206);
+    let policy = CachePolicy::new_options(&request, &response, now, CacheOptions {
+        understood_statuses,
+        ..Default::default()
+    });
+    assert!(!policy.is_storable());
+    assert!(!policy.before_request(&request, now).satisfies_without_revalidation());
+
+    let policy = CachePolicy::new_options(&request, &response, now, CacheOptions {
+        partial_content: true,
+        ..Default::default()
+    });
+    assert!(policy.is_storable());
+}
+
+#[test]
+fn test_qualified_private_strips_fields_in_shared_cache() {
+    let now = SystemTime::now();
+    let response = response_parts(
+        Response::builder()
+            .header(header::CACHE_CONTROL, "max-age=100, private=\"Set-Cookie, X-User\"")
+            .header(header::SET_COOKIE, "session=1")
+            .header("x-user", "bob")
+            .header("x-other", "kept"),
+    );
+
+    let policy = CachePolicy::new(&request_parts(Request::builder()), &response);
+    assert!(policy.is_storable());
+    assert!(!policy.is_stale(now));
+    let res = get_cached_response(&policy, &request_parts(Request::builder()), now);
+    assert!(!res.headers.contains_key(header::SET_COOKIE));
+    assert!(!res.headers.contains_key("x-user"));
+    assert_eq!(res.headers["x-other"], "kept");
+
+    let policy = CachePolicy::new_options(&request_parts(Request::builder()), &response, now, CacheOptions {
+        shared: false,
+        ..Default::default()
+    });
+    let res = get_cached_response(&policy, &request_parts(Request::builder()), now);
+    assert_eq!(res.headers[header::SET_COOKIE], "session=1");
+    assert_eq!(res.headers["x-user"], "bob");
+}
+
+#[test]
+fn test_qualified_no_cache_strips_fields() {
+    let now = SystemTime::now();
+    let policy = CachePolicy::new(
+        &request_parts(Request::builder()),
+        &response_parts(
+            Response::builder()
+                .header(header::CACHE_CONTROL, "max-age=100, no-cache=\"x-session\"")
+                .header("x-session", "abc")
+                .header("x-other", "kept"),
+        ),
+    );
+
+    assert!(policy.is_storable());
+    assert!(!policy.is_stale(now));
+    let res = get_cached_response(&policy, &request_parts(Request::builder()), now);
+    assert!(!res.headers.contains_key("x-session"));
+    assert_eq!(res.headers["x-other"], "kept");
+}
@@ -499,7 +499,7 @@ fn get_revalidation_request(
     now: SystemTime,
 ) -> http::request::Parts {
     match policy.before_request(req, now) {
-        http_cache_semantics::BeforeRequest::Stale { request, matches } => {
+        http_cache_semantics::BeforeRequest::Stale { request, matches, .. } => {
             if !matches {
                 eprintln!("warning: req doesn't match {req:#?} vs {policy:#?}");
             }
@@ -0,0 +1,214 @@
+use http::{header, Method, Request, Response};
+use http_cache_semantics::BeforeRequest;
+use http_cache_semantics::CacheOptions;
+use http_cache_semantics::CachePolicy;
+use http_cache_semantics::Override;
+use http_cache_semantics::Rule;
+use http_cache_semantics::RuleMatch;
+use http_cache_semantics::UpstreamError;
+use http_cache_semantics::VaryNormalization;
+use std::time::Duration;
+use std::time::SystemTime;
+
+fn request_parts(builder: http::request::Builder) -> http::request::Parts {
+    builder.body(()).unwrap().into_parts().0
+}
+
+fn response_parts(builder: http::response::Builder) -> http::response::Parts {
+    builder.body(()).unwrap().into_parts().0
+}
+
+fn rule(conditions: RuleMatch, apply: Override) -> CacheOptions {
+    CacheOptions { rules: vec![Rule { conditions, apply, ..Default::default() }], ..Default::default() }
+}
+
+fn applied(options: &CacheOptions, req: http::request::Builder, res: http::response::Builder) -> Option<String> {
+    let policy = CachePolicy::new_options(&request_parts(req), &response_parts(res), SystemTime::now(), options.clone());
+    policy.explain().applied_override.map(|o| o.name)
+}
+
+#[test]
+fn test_conditions() {
+    let options = rule(RuleMatch {
+        host: Some("*.example.com".into()),
+        path: Some("/static/*.js".into()),
+        methods: vec!["get".into()],
+        statuses: vec![200],
+        content_type: Some("application/*".into()),
+    }, Override::new("js"));
+    let js = || Response::builder().header(header::CONTENT_TYPE, "Application/JavaScript; charset=utf-8");
+    let req = |uri: &str| Request::builder().uri(uri);
+
+    assert_eq!(applied(&options, req("https://cdn.example.com/static/app/main.js?v=1"), js()).as_deref(), Some("js"));
+    // host from the Host header, without the port
+    assert_eq!(applied(&options, req("/static/app.js").header(header::HOST, "CDN.example.com:8080"), js()).as_deref(), Some("js"));
+
+    assert_eq!(applied(&options, req("https://example.org/static/app.js"), js()), None);
+    assert_eq!(applied(&options, req("/static/app.js"), js()), None);
+    assert_eq!(applied(&options, req("https://cdn.example.com/static/app.css"), js()), None);
+    assert_eq!(applied(&options, req("https://cdn.example.com/Static/app.js"), js()), None);
+    assert_eq!(applied(&options, req("https://cdn.example.com/static/app.js").method(Method::HEAD), js()), None);
+    assert_eq!(applied(&options, req("https://cdn.example.com/static/app.js"), js().status(404)), None);
+    assert_eq!(applied(&options, req("https://cdn.example.com/static/app.js"), Response::builder()), None);
+}
+
+#[test]
+fn test_first_match() {
+    let mut options = rule(RuleMatch { path: Some("/api/*".into()), ..Default::default() }, Override::new("api"));
+    options.rules.push(Rule { apply: Override::new("everything"), ..Default::default() });
+    assert_eq!(applied(&options, Request::builder().uri("/api/v1/items"), Response::builder()).as_deref(), Some("api"));
+    assert_eq!(applied(&options, Request::builder().uri("/"), Response::builder()).as_deref(), Some("everything"));
+}
+
+#[test]
+fn test_strip_headers() {
+    let options = rule(RuleMatch::default(), Override {
+        strip_headers: vec!["Set-Cookie".into(), "x-debug".into()],
+        ..Override::new("strip")
+    });
+    let res = response_parts(Response::builder()
+        .header(header::CACHE_CONTROL, "max-age=60")
+        .header(header::SET_COOKIE, "session=1")
+        .header("x-debug", "1"));
+    let req = request_parts(Request::builder().uri("/"));
+    let policy = CachePolicy::new_options(&req, &res, SystemTime::now(), options);
+    let BeforeRequest::Fresh(parts) = policy.before_request(&req, SystemTime::now()) else {
+        panic!("Set-Cookie shouldn't prevent reuse");
+    };
+    assert!(!parts.headers.contains_key(header::SET_COOKIE));
+    assert!(!parts.headers.contains_key("x-debug"));
+}
+
+#[test]
+fn test_grace() {
+    let now = SystemTime::now();
+    let options = rule(RuleMatch::default(), Override { grace: Some(Duration::from_secs(60)), ..Override::new("grace") });
+    let req = request_parts(Request::builder().uri("/"));
+    let res = response_parts(Response::builder().header(header::CACHE_CONTROL, "max-age=10"));
+    let policy = CachePolicy::new_options(&req, &res, now, options);
+
+    let later = now + Duration::from_secs(30);
+    assert!(matches!(policy.before_request(&req, later), BeforeRequest::StaleWhileRevalidate { .. }));
+    assert!(policy.on_upstream_error(&req, UpstreamError::Unreachable, later).is_some());
+
+    let too_late = now + Duration::from_secs(100);
+    assert!(matches!(policy.before_request(&req, too_late), BeforeRequest::Stale { .. }));
+    assert!(policy.on_upstream_error(&req, UpstreamError::Unreachable, too_late).is_none());
+
+    let only_if_cached = request_parts(Request::builder().uri("/").header(header::CACHE_CONTROL, "only-if-cached"));
+    assert!(matches!(policy.before_request(&only_if_cached, later), BeforeRequest::StaleWhileRevalidate { .. }));
+    assert!(matches!(policy.before_request(&only_if_cached, too_late), BeforeRequest::GatewayTimeout(_)));
+}
+
+/// Normalizes `Accept-Encoding` for all responses to a status the rule doesn't match
+fn vary_rule() -> CacheOptions {
+    CacheOptions {
+        rules: vec![Rule {
+            conditions: RuleMatch { statuses: vec![404], ..Default::default() },
+            vary_normalization: vec![VaryNormalization::AcceptEncoding],
+            apply: Override::new("vary"),
+        }],
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_vary_normalization() {
+    let options = vary_rule();
+    let req = request_parts(Request::builder().uri("/").header(header::ACCEPT_ENCODING, "gzip, br"));
+    let res = response_parts(Response::builder().header(header::CACHE_CONTROL, "max-age=60").header(header::VARY, "accept-encoding"));
+    let policy = CachePolicy::new_options(&req, &res, SystemTime::now(), options);
+
+    let other = request_parts(Request::builder().uri("/").header(header::ACCEPT_ENCODING, "br;q=1, deflate"));
+    assert!(policy.before_request(&other, SystemTime::now()).satisfies_without_revalidation());
+    let identity = request_parts(Request::builder().uri("/").header(header::ACCEPT_ENCODING, "identity"));
+    assert!(!policy.before_request(&identity, SystemTime::now()).satisfies_without_revalidation());
+}
+
+#[test]
+fn test_vary_normalization_secondary_key() {
+    let options = vary_rule();
+    let req = request_parts(Request::builder().uri("https://example.com/").header(header::ACCEPT_ENCODING, "gzip, br"));
+    let res = response_parts(Response::builder().header(header::CACHE_CONTROL, "max-age=60").header(header::VARY, "accept-encoding"));
+    let policy = CachePolicy::new_options(&req, &res, SystemTime::now(), options.clone());
+    assert_eq!(policy.explain().applied_override, None);
+
+    let other = request_parts(Request::builder().uri("https://example.com/").header(header::ACCEPT_ENCODING, "br;q=1, deflate"));
+    assert!(policy.secondary_key().is_some());
+    assert_eq!(policy.secondary_key(), options.secondary_key_for_request(&req, ["accept-encoding"]));
+    assert_eq!(policy.secondary_key(), options.secondary_key_for_request(&other, ["accept-encoding"]));
+
+    // a rule for another path doesn't normalize
+    let mut options = vary_rule();
+    options.rules[0].conditions.path = Some("/images/*".into());
+    let policy = CachePolicy::new_options(&req, &res, SystemTime::now(), options.clone());
+    assert_eq!(policy.secondary_key(), options.secondary_key_for_request(&req, ["accept-encoding"]));
+    assert_ne!(policy.secondary_key(), options.secondary_key_for_request(&other, ["accept-encoding"]));
+}
+
+#[test]
+#[cfg(feature = "serde")]
+fn test_deserialize() {
+    use http_cache_semantics::AfterResponse;
+
+    let options: CacheOptions = serde_json::from_str(r#"{
+        "shared": false,
+        "rules": [{
+            "name": "api",
+            "match": {"host": "api.example.com", "path": "/v1/*", "methods": ["GET"]},
+            "ttl": 30,
+            "grace": 300,
+            "storable": true,
+            "vary_normalization": ["accept-encoding", "device-class"],
+            "strip_headers": ["set-cookie"]
+        }]
+    }"#).unwrap();
+    assert!(!options.shared);
+    assert_eq!(options.rules, [Rule {
+        conditions: RuleMatch {
+            host: Some("api.example.com".into()),
+            path: Some("/v1/*".into()),
+            methods: vec!["GET".into()],
+            ..Default::default()
+        },
+        vary_normalization: vec![VaryNormalization::AcceptEncoding, VaryNormalization::DeviceClass],
+        apply: Override {
+            storable: Some(true),
+            ttl: Some(Duration::from_secs(30)),
+            grace: Some(Duration::from_secs(300)),
+            strip_headers: vec!["set-cookie".into()],
+            ..Override::new("api")
+        },
+    }]);
+
+    let req = request_parts(Request::builder().uri("https://api.example.com/v1/items"));
+    let res = response_parts(Response::builder().header(header::CACHE_CONTROL, "no-store"));
+    let policy = CachePolicy::new_options(&req, &res, SystemTime::now(), options.clone());
+    assert!(policy.is_storable());
+    assert_eq!(policy.explain().freshness_lifetime, Duration::from_secs(30));
+
+    // the rules are kept with the policy
+    let policy: CachePolicy = serde_json::from_str(&serde_json::to_string(&policy).unwrap()).unwrap();
+    let new_res = response_parts(Response::builder().header(header::CACHE_CONTROL, "no-store"));
+    let AfterResponse::Modified(revalidated, _) = policy.after_response(&req, &new_res, SystemTime::now()) else {
+        panic!("expected modified");
+    };
+    assert_eq!(revalidated.explain().applied_override.map(|o| o.name).as_deref(), Some("api"));
+
+    let json = serde_json::to_value(&options.rules).unwrap();
+    assert_eq!(json[0]["ttl"], 30);
+    assert_eq!(serde_json::from_value::<Vec<Rule>>(json).unwrap(), options.rules);
+}
+
+#[test]
+#[cfg(feature = "serde")]
+fn test_deserialize_unknown_keys() {
+    let rule: Rule = serde_json::from_str(r#"{"name": "all"}"#).unwrap();
+    assert_eq!(rule, Rule { apply: Override::new("all"), ..Default::default() });
+
+    // a misspelled condition would otherwise match every response
+    let err = serde_json::from_str::<Rule>(r#"{"name": "private", "match": {"paht": "/account/*"}, "storable": false}"#).unwrap_err();
+    assert!(err.to_string().contains("paht"), "{err}");
+    let err = serde_json::from_str::<Rule>(r#"{"name": "api", "match": {"path": "/v1/*"}, "tll": 30}"#).unwrap_err();
+    assert!(err.to_string().contains("tll"), "{err}");
+}
@@ -1,6 +1,11 @@
+#![allow(clippy::unnecessary_mut_passed)]
+
 use http::{header, Method, Request, Response};
+use http_cache_semantics::BeforeRequest;
 use http_cache_semantics::CacheOptions;
 use http_cache_semantics::CachePolicy;
+use http_cache_semantics::CacheabilityRule;
+use http_cache_semantics::StaleReason;
 use std::time::SystemTime;
 use time::format_description::well_known::Rfc2822;
 use time::Duration;
@@ -240,3 +245,55 @@ fn test_not_when_no_cache_requesting() {
         )
         .satisfies_without_revalidation());
 }
+
+fn stale_reason(policy: &CachePolicy, req: &http::request::Parts, now: SystemTime) -> StaleReason {
+    match policy.before_request(req, now) {
+        BeforeRequest::Stale { reason, .. } => reason,
+        _ => panic!("expected stale"),
+    }
+}
+
+#[test]
+fn test_stale_reasons() {
+    let now = SystemTime::now();
+    let response = &response_parts(Response::builder().header(header::CACHE_CONTROL, "max-age=2"));
+    let policy = CachePolicy::new(&request_parts(Request::builder().uri("/")), response);
+
+    let reason = stale_reason(&policy, &request_parts(Request::builder().uri("/other")), now);
+    assert_eq!(reason, StaleReason::UriMismatch);
+    assert_eq!(reason.cache_status_fwd(), "uri-miss");
+    assert_eq!(
+        stale_reason(&policy, &request_parts(Request::builder().uri("/").method(Method::POST)), now),
+        StaleReason::MethodMismatch
+    );
+    let reason = stale_reason(&policy, &request_parts(Request::builder().uri("/").header(header::PRAGMA, "no-cache")), now);
+    assert_eq!(reason, StaleReason::RequestNoCache);
+    assert_eq!(reason.cache_status_fwd(), "request");
+    assert_eq!(
+        stale_reason(&policy, &request_parts(Request::builder().uri("/").header(header::CACHE_CONTROL, "min-fresh=10")), now),
+        StaleReason::RequestMinFresh
+    );
+    assert_eq!(
+        stale_reason(
+            &policy,
+            &request_parts(Request::builder().uri("/").header(header::CACHE_CONTROL, "max-age=0")),
+            now + std::time::Duration::from_secs(1)
+        ),
+        StaleReason::RequestMaxAge
+    );
+    let reason = stale_reason(&policy, &request_parts(Request::builder().uri("/")), now + std::time::Duration::from_secs(3));
+    assert_eq!(reason, StaleReason::Expired);
+    assert!(!reason.is_mismatch());
+}
+
+#[test]
+fn test_stale_reason_not_reusable() {
+    let now = SystemTime::now();
+    let response = &response_parts(Response::builder().header(header::CACHE_CONTROL, "max-age=2, no-cache"));
+    let policy = CachePolicy::new(&request_parts(Request::builder()), response);
+
+    assert_eq!(
+        stale_reason(&policy, &request_parts(Request::builder()), now),
+        StaleReason::NotReusable(CacheabilityRule::NoCache)
+    );
+}
@@ -0,0 +1,196 @@
+use http::{header, Request, Response, StatusCode};
+use http_cache_semantics::AfterResponse;
+use http_cache_semantics::BeforeRequest;
+use http_cache_semantics::CacheOptions;
+use http_cache_semantics::CachePolicy;
+use http_cache_semantics::UpstreamError;
+use std::time::Duration;
+use std::time::SystemTime;
+
+fn request_parts(builder: http::request::Builder) -> http::request::Parts {
+    builder.body(()).unwrap().into_parts().0
+}
+
+fn response_parts(builder: http::response::Builder) -> http::response::Parts {
+    builder.body(()).unwrap().into_parts().0
+}
+
+fn swr_policy(cache_control: &str, now: SystemTime) -> CachePolicy {
+    CachePolicy::new_options(
+        &request_parts(Request::builder().uri("/")),
+        &response_parts(
+            Response::builder()
+                .header(header::CACHE_CONTROL, cache_control)
+                .header(header::ETAG, "\"v1\""),
+        ),
+        now,
+        CacheOptions::default(),
+    )
+}
+
+#[test]
+fn test_fresh_response_ignores_swr() {
+    let now = SystemTime::now();
+    let policy = swr_policy("max-age=10, stale-while-revalidate=60", now);
+
+    assert!(matches!(
+        policy.before_request(&request_parts(Request::builder().uri("/")), now + Duration::from_secs(5)),
+        BeforeRequest::Fresh(_)
+    ));
+}
+
+#[test]
+fn test_stale_within_swr_window() {
+    let now = SystemTime::now();
+    let policy = swr_policy("max-age=10, stale-while-revalidate=60", now);
+
+    match policy.before_request(&request_parts(Request::builder().uri("/")), now + Duration::from_secs(30)) {
+        BeforeRequest::StaleWhileRevalidate { response, revalidation_request } => {
+            assert_eq!(response.headers[header::AGE], "30");
+            assert_eq!(revalidation_request.headers[header::IF_NONE_MATCH], "\"v1\"");
+            assert_eq!(revalidation_request.uri, "/");
+        },
+        _ => panic!("expected stale-while-revalidate"),
+    }
+}
+
+#[test]
+fn test_stale_past_swr_window() {
+    let now = SystemTime::now();
+    let policy = swr_policy("max-age=10, stale-while-revalidate=60", now);
+
+    assert!(matches!(
+        policy.before_request(&request_parts(Request::builder().uri("/")), now + Duration::from_secs(71)),
+        BeforeRequest::Stale { matches: true, .. }
+    ));
+}
+
+#[test]
+fn test_swr_not_with_must_revalidate() {
+    let now = SystemTime::now();
+    let policy = swr_policy("max-age=10, stale-while-revalidate=60, must-revalidate", now);
+
+    assert!(matches!(
+        policy.before_request(&request_parts(Request::builder().uri("/")), now + Duration::from_secs(30)),
+        BeforeRequest::Stale { .. }
+    ));
+}
+
+#[test]
+fn test_swr_not_with_request_no_cache() {
+    let now = SystemTime::now();
+    let policy = swr_policy("max-age=10, stale-while-revalidate=60", now);
+
+    assert!(matches!(
+        policy.before_request(
+            &request_parts(Request::builder().uri("/").header(header::CACHE_CONTROL, "no-cache")),
+            now + Duration::from_secs(30)
+        ),
+        BeforeRequest::Stale { .. }
+    ));
+}
+
+#[test]
+fn test_swr_not_for_other_uri() {
+    let now = SystemTime::now();
+    let policy = swr_policy("max-age=10, stale-while-revalidate=60", now);
+
+    assert!(matches!(
+        policy.before_request(&request_parts(Request::builder().uri("/other")), now + Duration::from_secs(30)),
+        BeforeRequest::Stale { matches: false, .. }
+    ));
+}
+
+#[test]
+fn test_stale_if_error_response_directive() {
+    let now = SystemTime::now();
+    let policy = swr_policy("max-age=10, stale-if-error=60", now);
+    let req = request_parts(Request::builder().uri("/"));
+
+    let later = now + Duration::from_secs(30);
+    let res = policy.on_upstream_error(&req, UpstreamError::Unreachable, later).unwrap();
+    assert_eq!(res.headers[header::AGE], "30");
+    assert!(policy.on_upstream_error(&req, UpstreamError::Status(StatusCode::BAD_GATEWAY), later).is_some());
+    assert!(policy.on_upstream_error(&req, UpstreamError::Status(StatusCode::NOT_FOUND), later).is_none());
+    assert!(policy.on_upstream_error(&req, UpstreamError::Unreachable, now + Duration::from_secs(71)).is_none());
+}
+
+#[test]
+fn test_stale_if_error_request_directive() {
+    let now = SystemTime::now();
+    let policy = swr_policy("max-age=10", now);
+    let later = now + Duration::from_secs(30);
+
+    assert!(policy.on_upstream_error(&request_parts(Request::builder().uri("/")), UpstreamError::Unreachable, later).is_none());
+    let req = request_parts(Request::builder().uri("/").header(header::CACHE_CONTROL, "stale-if-error=100"));
+    assert!(policy.on_upstream_error(&req, UpstreamError::Unreachable, later).is_some());
+    let req = request_parts(Request::builder().uri("/").header(header::CACHE_CONTROL, "stale-if-error=5"));
+    assert!(policy.on_upstream_error(&req, UpstreamError::Unreachable, later).is_none());
+}
+
+#[test]
+fn test_stale_if_error_not_with_must_revalidate() {
+    let now = SystemTime::now();
+    let policy = swr_policy("max-age=10, stale-if-error=60, must-revalidate", now);
+    let req = request_parts(Request::builder().uri("/"));
+
+    assert!(policy.on_upstream_error(&req, UpstreamError::Unreachable, now + Duration::from_secs(30)).is_none());
+}
+
+#[test]
+fn test_after_response_upstream_error() {
+    let now = SystemTime::now();
+    let policy = swr_policy("max-age=10, stale-if-error=60", now);
+    let later = now + Duration::from_secs(30);
+    let req = request_parts(Request::builder().uri("/"));
+    let BeforeRequest::Stale { request, .. } = policy.before_request(&req, later) else {
+        panic!("should be stale");
+    };
+
+    let error = response_parts(Response::builder().status(503));
+    match policy.after_response(&request, &error, later) {
+        AfterResponse::UpstreamError(Some(res)) => {
+            assert_eq!(res.status, StatusCode::OK);
+            assert_eq!(res.headers[header::ETAG], "\"v1\"");
+        },
+        _ => panic!("expected stale response"),
+    }
+
+    match policy.after_response(&request, &error, now + Duration::from_secs(100)) {
+        AfterResponse::UpstreamError(None) => {},
+        _ => panic!("expected error to be passed on"),
+    }
+}
+
+#[test]
+fn test_only_if_cached() {
+    let now = SystemTime::now();
+    let policy = swr_policy("max-age=10, stale-while-revalidate=60", now);
+    let req = request_parts(Request::builder().uri("/").header(header::CACHE_CONTROL, "only-if-cached"));
+
+    assert!(matches!(policy.before_request(&req, now + Duration::from_secs(5)), BeforeRequest::Fresh(_)));
+    assert!(matches!(policy.before_request(&req, now + Duration::from_secs(30)), BeforeRequest::StaleWhileRevalidate { .. }));
+    match policy.before_request(&req, now + Duration::from_secs(100)) {
+        BeforeRequest::GatewayTimeout(res) => {
+            assert_eq!(res.status, StatusCode::GATEWAY_TIMEOUT);
+            assert!(res.headers.contains_key(header::DATE));
+            assert!(!res.headers.contains_key(header::ETAG));
+        },
+        _ => panic!("expected 504"),
+    }
+
+    let other = request_parts(Request::builder().uri("/other").header(header::CACHE_CONTROL, "only-if-cached"));
+    assert!(matches!(policy.before_request(&other, now), BeforeRequest::GatewayTimeout(_)));
+}
+
+#[test]
+fn test_only_if_cached_with_max_stale() {
+    let now = SystemTime::now();
+    let policy = swr_policy("max-age=10", now);
+    let later = now + Duration::from_secs(30);
+
+    let req = request_parts(Request::builder().uri("/").header(header::CACHE_CONTROL, "only-if-cached, max-stale=60"));
+    assert!(matches!(policy.before_request(&req, later), BeforeRequest::Fresh(_)));
+    let req = request_parts(Request::builder().uri("/").header(header::CACHE_CONTROL, "only-if-cached, max-stale=5"));
+    assert!(matches!(policy.before_request(&req, later), BeforeRequest::GatewayTimeout(_)));
+}
@@ -0,0 +1,154 @@
+use http::{header, Request, Response};
+use http_cache_semantics::BeforeRequest;
+use http_cache_semantics::CacheOptions;
+use http_cache_semantics::CachePolicy;
+use std::time::SystemTime;
+
+fn request_parts(builder: http::request::Builder) -> http::request::Parts {
+    builder.body(()).unwrap().into_parts().0
+}
+
+fn response_parts(builder: http::response::Builder) -> http::response::Parts {
+    builder.body(()).unwrap().into_parts().0
+}
+
+fn cdn_options() -> CacheOptions {
+    CacheOptions {
+        targeted_fields: vec!["acme-cache-control".into(), "cdn-cache-control".into()],
+        ..Default::default()
+    }
+}
+
+fn ttl(response: http::response::Builder, opts: CacheOptions) -> u64 {
+    let now = SystemTime::now();
+    let policy = CachePolicy::new_options(&request_parts(Request::builder()), &response_parts(response), now, opts);
+    policy.time_to_live(now).as_secs()
+}
+
+#[test]
+fn test_targeted_field_overrides_cache_control() {
+    let response = || {
+        Response::builder()
+            .header(header::CACHE_CONTROL, "max-age=60")
+            .header("cdn-cache-control", "max-age=600")
+    };
+    assert_eq!(ttl(response(), cdn_options()), 600);
+    assert_eq!(ttl(response(), CacheOptions::default()), 60);
+}
+
+#[test]
+fn test_targeted_field_precedence() {
+    let response = Response::builder()
+        .header(header::CACHE_CONTROL, "max-age=60")
+        .header("cdn-cache-control", "max-age=600")
+        .header("acme-cache-control", "max-age=6000");
+    assert_eq!(ttl(response, cdn_options()), 6000);
+}
+
+#[test]
+fn test_invalid_targeted_field_is_ignored() {
+    let response = Response::builder()
+        .header(header::CACHE_CONTROL, "max-age=60")
+        .header("acme-cache-control", "max-age=\"6000")
+        .header("cdn-cache-control", "max-age=600");
+    assert_eq!(ttl(response, cdn_options()), 600);
+
+    let response = Response::builder()
+        .header(header::CACHE_CONTROL, "max-age=60")
+        .header("cdn-cache-control", "Max-Age=600");
+    assert_eq!(ttl(response, cdn_options()), 60);
+}
+
+#[test]
+fn test_targeted_field_ignores_expires() {
+    let response = Response::builder()
+        .header(header::DATE, "Mon, 01 Jan 2024 00:00:00 GMT")
+        .header(header::EXPIRES, "Mon, 01 Jan 2024 01:00:00 GMT")
+        .header("cdn-cache-control", "public");
+    let now = SystemTime::now();
+    let policy = CachePolicy::new_options(&request_parts(Request::builder()), &response_parts(response), now, cdn_options());
+    assert!(policy.is_storable());
+    assert_eq!(policy.explain().freshness_source, http_cache_semantics::FreshnessSource::None);
+}
+
+#[test]
+fn test_targeted_no_store() {
+    let now = SystemTime::now();
+    let response = Response::builder()
+        .header(header::CACHE_CONTROL, "max-age=60")
+        .header("cdn-cache-control", "no-store");
+    let policy = CachePolicy::new_options(&request_parts(Request::builder()), &response_parts(response), now, cdn_options());
+    assert!(!policy.is_storable());
+}
+
+fn surrogate_options() -> CacheOptions {
+    CacheOptions {
+        surrogate_control: true,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_surrogate_control_overrides_cache_control() {
+    let response = || {
+        Response::builder()
+            .header(header::CACHE_CONTROL, "private, max-age=60")
+            .header("surrogate-control", "max-age=3600+600, content=\"ESI/1.0\"")
+    };
+    assert_eq!(ttl(response(), surrogate_options()), 3600);
+    assert_eq!(ttl(response(), CacheOptions::default()), 0);
+}
+
+#[test]
+fn test_surrogate_control_no_store() {
+    let now = SystemTime::now();
+    let response = Response::builder()
+        .header(header::CACHE_CONTROL, "max-age=60")
+        .header("surrogate-control", "no-store");
+    let policy = CachePolicy::new_options(&request_parts(Request::builder()), &response_parts(response), now, surrogate_options());
+    assert!(!policy.is_storable());
+}
+
+#[test]
+fn test_surrogate_control_without_known_directives_is_ignored() {
+    let response = Response::builder()
+        .header(header::CACHE_CONTROL, "max-age=60")
+        .header("surrogate-control", "content=\"ESI/1.0\", max-age=600;other-device");
+    assert_eq!(ttl(response, surrogate_options()), 60);
+}
+
+#[test]
+fn test_targeted_field_overrides_surrogate_control() {
+    let response = Response::builder()
+        .header("cdn-cache-control", "max-age=600")
+        .header("surrogate-control", "max-age=3600");
+    let opts = CacheOptions {
+        surrogate_control: true,
+        ..cdn_options()
+    };
+    assert_eq!(ttl(response, opts), 600);
+}
+
+#[test]
+fn test_surrogate_control_removed_from_cached_response() {
+    let now = SystemTime::now();
+    let response = || {
+        Response::builder()
+            .header(header::CACHE_CONTROL, "max-age=60")
+            .header("surrogate-control", "max-age=3600")
+    };
+    let req = request_parts(Request::builder());
+
+    let policy = CachePolicy::new_options(&req, &response_parts(response()), now, surrogate_options());
+    let BeforeRequest::Fresh(res) = policy.before_request(&req, now) else {
+        panic!("should be fresh");
+    };
+    assert!(!res.headers.contains_key("surrogate-control"));
+    assert_eq!(res.headers[header::CACHE_CONTROL], "max-age=60");
+
+    let policy = CachePolicy::new_options(&req, &response_parts(response()), now, CacheOptions::default());
+    let BeforeRequest::Fresh(res) = policy.before_request(&req, now) else {
+        panic!("should be fresh");
+    };
+    assert_eq!(res.headers["surrogate-control"], "max-age=3600");
+}
@@ -2,6 +2,8 @@
 //! cached response can be reused, following the rules specified in [RFC
 //! 7234](https://httpwg.org/specs/rfc7234.html).
 
+#![allow(clippy::bool_assert_comparison)]
+
 use http::header::HeaderName;
 use http::header::HeaderValue;
 use http::Request;
@@ -1808,3 +1810,21 @@ fn test_order_is_irrelevant() {
         )
         .satisfies_without_revalidation());
 }
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_options_missing_from_serialized_policy_use_defaults() {
+    let now = SystemTime::now();
+    let policy = CachePolicy::new_options(
+        &req(json!({})),
+        &res(json!({ "headers": { "cache-control": "max-age=100" } })),
+        now,
+        Default::default(),
+    );
+
+    let mut json = serde_json::to_value(&policy).unwrap();
+    json["opts"] = json!({ "shared": false });
+    let policy: CachePolicy = serde_json::from_value(json).unwrap();
+    assert!(policy.is_storable());
+    assert!(!policy.is_stale(now));
+}
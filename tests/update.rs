@@ -1,3 +1,5 @@
+#![allow(clippy::single_match)]
+
 use http::header::HeaderName;
 use http::request::Parts as RequestParts;
 use http::{header, HeaderMap, Request, Response};
@@ -81,6 +83,7 @@ fn not_modified_response_headers_for_update(
     match rev {
         AfterResponse::Modified(..) => None,
         AfterResponse::NotModified(_, res) => Some(res.headers),
+        _ => None,
     }
 }
 
@@ -132,7 +135,7 @@ fn get_revalidation_request(
     now: SystemTime,
 ) -> http::request::Parts {
     match policy.before_request(req, now) {
-        http_cache_semantics::BeforeRequest::Stale { request, matches } => {
+        http_cache_semantics::BeforeRequest::Stale { request, matches, .. } => {
             if !matches {
                 eprintln!("warning: req doesn't match {req:#?} vs {policy:?}");
             }
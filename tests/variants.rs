@@ -0,0 +1,207 @@
+use http::{header, Request, Response};
+use http_cache_semantics::AfterResponse;
+use http_cache_semantics::BeforeRequest;
+use http_cache_semantics::CachePolicy;
+use http_cache_semantics::VariantSet;
+use std::time::Duration;
+use std::time::SystemTime;
+
+fn request_parts(builder: http::request::Builder) -> http::request::Parts {
+    builder.body(()).unwrap().into_parts().0
+}
+
+fn response_parts(builder: http::response::Builder) -> http::response::Parts {
+    builder.body(()).unwrap().into_parts().0
+}
+
+fn req(encoding: &str) -> http::request::Parts {
+    request_parts(Request::builder().uri("/page").header(header::ACCEPT_ENCODING, encoding))
+}
+
+fn variant(encoding: &str, etag: &str, date: SystemTime, now: SystemTime) -> CachePolicy {
+    let res = Response::builder()
+        .header(header::CACHE_CONTROL, "max-age=100")
+        .header(header::VARY, "accept-encoding")
+        .header(header::ETAG, etag)
+        .header(header::DATE, http_cache_semantics::HttpDate::from(date).to_string());
+    CachePolicy::new_options(&req(encoding), &response_parts(res), now, Default::default())
+}
+
+fn set(now: SystemTime) -> VariantSet<&'static str> {
+    let mut set = VariantSet::new(variant("gzip", "\"gz\"", now, now), "gzip body");
+    assert!(set.insert(variant("br", "W/\"br\"", now, now), "br body").is_none());
+    set
+}
+
+#[test]
+fn test_select_by_vary() {
+    let now = SystemTime::now();
+    let set = set(now);
+    assert_eq!(set.len(), 2);
+    assert_eq!(set.select(&req("br")).unwrap().1, &"br body");
+    assert_eq!(set.select(&req("gzip")).unwrap().1, &"gzip body");
+    assert!(set.select(&req("identity")).is_none());
+
+    match set.before_request(&req("br"), now) {
+        (BeforeRequest::Fresh(_), Some(&"br body")) => {},
+        _ => panic!("expected fresh br"),
+    }
+}
+
+#[test]
+fn test_insert_replaces_same_variant() {
+    let now = SystemTime::now();
+    let mut set = set(now);
+    let old = set.insert(variant("gzip", "\"gz2\"", now, now), "new gzip body");
+    assert_eq!(old, Some("gzip body"));
+    assert_eq!(set.len(), 2);
+    assert_eq!(set.select(&req("gzip")).unwrap().1, &"new gzip body");
+}
+
+#[test]
+fn test_select_most_recent() {
+    let now = SystemTime::now();
+    let older = now - Duration::from_secs(10);
+    let policy = |req: &http::request::Parts, vary: &str, etag: &str, date: SystemTime| {
+        let res = response_parts(Response::builder()
+            .header(header::CACHE_CONTROL, "max-age=100")
+            .header(header::VARY, vary)
+            .header(header::ETAG, etag)
+            .header(header::DATE, http_cache_semantics::HttpDate::from(date).to_string()));
+        CachePolicy::new_options(req, &res, now, Default::default())
+    };
+    let both = request_parts(Request::builder().uri("/page")
+        .header(header::ACCEPT_ENCODING, "gzip")
+        .header(header::ACCEPT_LANGUAGE, "en"));
+    let mut set = VariantSet::new(policy(&req("gzip"), "accept-encoding", "\"old\"", older), "old");
+    set.insert(policy(&both, "accept-language", "\"new\"", now), "new");
+    assert_eq!(set.len(), 2);
+    assert_eq!(set.select(&both).unwrap().1, &"new");
+    assert_eq!(set.select(&req("gzip")).unwrap().1, &"old");
+}
+
+#[test]
+fn test_revalidation_lists_all_etags() {
+    let now = SystemTime::now();
+    let set = set(now);
+    let later = now + Duration::from_secs(200);
+
+    let (before, data) = set.before_request(&req("br"), later);
+    assert_eq!(data, Some(&"br body"));
+    let BeforeRequest::Stale { request, matches, .. } = before else {
+        panic!("expected stale");
+    };
+    assert!(matches);
+    assert_eq!(request.headers[header::IF_NONE_MATCH], "W/\"br\", \"gz\"");
+
+    // a variant that isn't stored yet can still be revalidated with the others' ETags
+    let (before, data) = set.before_request(&req("identity"), later);
+    assert!(data.is_none());
+    let BeforeRequest::Stale { request, matches, .. } = before else {
+        panic!("expected stale");
+    };
+    assert!(!matches);
+    assert_eq!(request.headers[header::IF_NONE_MATCH], "\"gz\", W/\"br\"");
+}
+
+#[test]
+fn test_no_weak_etags_for_range_requests() {
+    let now = SystemTime::now();
+    let set = set(now);
+    let ranged = request_parts(Request::builder().uri("/page").header(header::ACCEPT_ENCODING, "gzip").header(header::RANGE, "bytes=0-10"));
+    let (before, _) = set.before_request(&ranged, now + Duration::from_secs(200));
+    let BeforeRequest::Stale { request, .. } = before else {
+        panic!("expected stale");
+    };
+    assert_eq!(request.headers[header::IF_NONE_MATCH], "\"gz\"");
+}
+
+#[test]
+fn test_not_modified_updates_by_etag() {
+    let now = SystemTime::now();
+    let mut set = set(now);
+    let later = now + Duration::from_secs(200);
+
+    // the client asked for identity, but the server says gzip is still valid
+    let not_modified = response_parts(Response::builder().status(304).header(header::ETAG, "\"gz\"").header(header::CACHE_CONTROL, "max-age=500"));
+    let (after, data) = set.after_response(&req("identity"), &not_modified, later);
+    let AfterResponse::NotModified(policy, _) = after else {
+        panic!("expected not modified");
+    };
+    assert_eq!(data.map(|d| *d), Some("gzip body"));
+    assert_eq!(policy.time_to_live(later), Duration::from_secs(500));
+
+    // the stored gzip variant is fresh again, and still only for gzip requests
+    assert!(matches!(set.before_request(&req("gzip"), later).0, BeforeRequest::Fresh(_)));
+    assert!(set.select(&req("identity")).is_none());
+    assert!(matches!(set.before_request(&req("br"), later).0, BeforeRequest::Stale { .. }));
+}
+
+#[test]
+fn test_not_modified_updates_by_weak_etag() {
+    let now = SystemTime::now();
+    let mut set = set(now);
+    let later = now + Duration::from_secs(200);
+
+    let not_modified = response_parts(Response::builder().status(304).header(header::ETAG, "W/\"br\""));
+    let (after, data) = set.after_response(&req("br"), &not_modified, later);
+    assert!(matches!(after, AfterResponse::NotModified(..)));
+    assert_eq!(data.map(|d| *d), Some("br body"));
+}
+
+#[test]
+fn test_not_modified_without_match() {
+    let now = SystemTime::now();
+    let mut set = set(now);
+    let not_modified = response_parts(Response::builder().status(304).header(header::ETAG, "\"other\""));
+    let (after, data) = set.after_response(&req("gzip"), &not_modified, now);
+    let AfterResponse::UnmatchedNotModified(res) = after else {
+        panic!("expected unmatched 304");
+    };
+    assert_eq!(res.status, 304);
+    assert_eq!(res.headers[header::ETAG], "\"other\"");
+    assert!(data.is_none());
+    assert_eq!(set.len(), 2);
+}
+
+#[test]
+fn test_remove() {
+    let now = SystemTime::now();
+    let set = set(now);
+    assert!(!set.is_empty());
+
+    let (set, data) = set.remove(&req("identity"));
+    assert!(data.is_none());
+    let (set, data) = set.unwrap().remove(&req("br"));
+    assert_eq!(data, Some("br body"));
+    let set = set.unwrap();
+    assert_eq!(set.len(), 1);
+    assert!(set.select(&req("br")).is_none());
+
+    let (set, data) = set.remove(&req("gzip"));
+    assert_eq!(data, Some("gzip body"));
+    assert!(set.is_none());
+}
+
+#[test]
+#[cfg(feature = "serde")]
+fn test_deserialize_empty() {
+    let now = SystemTime::now();
+    let json = serde_json::to_string(&set(now)).unwrap();
+    let set: VariantSet<String> = serde_json::from_str(&json).unwrap();
+    assert_eq!(set.len(), 2);
+    assert!(serde_json::from_str::<VariantSet<String>>(r#"{"variants": []}"#).is_err());
+}
+
+#[test]
+fn test_head_response_updates_variants() {
+    let now = SystemTime::now();
+    let mut set = set(now);
+    let later = now + Duration::from_secs(200);
+
+    let head_req = request_parts(Request::builder().method(http::Method::HEAD).uri("/page").header(header::ACCEPT_ENCODING, "gzip"));
+    let head = response_parts(Response::builder().header(header::ETAG, "\"gz\"").header(header::CACHE_CONTROL, "max-age=500"));
+    assert!(set.after_head_response(&head_req, &head, later));
+    assert!(matches!(set.before_request(&req("gzip"), later).0, BeforeRequest::Fresh(_)));
+    assert!(matches!(set.before_request(&req("br"), later).0, BeforeRequest::Stale { .. }));
+}
@@ -1,6 +1,14 @@
-use http::{header, Request, Response};
+use http::{header, HeaderName, HeaderValue, Request, Response};
+use http_cache_semantics::AcceptEncodingNormalizer;
+use http_cache_semantics::AcceptLanguageNormalizer;
+use http_cache_semantics::BeforeRequest;
+use http_cache_semantics::CacheOptions;
 use http_cache_semantics::CachePolicy;
+use http_cache_semantics::DeviceClassNormalizer;
+use http_cache_semantics::StaleReason;
+use http_cache_semantics::VaryNormalizer;
 
+use std::sync::Arc;
 use std::time::SystemTime;
 
 fn request_parts(builder: http::request::Builder) -> http::request::Parts {
@@ -351,3 +359,259 @@ fn test_order_is_irrelevant() {
         )
         .satisfies_without_revalidation());
 }
+
+#[test]
+fn test_vary_mismatch_reason() {
+    let now = SystemTime::now();
+    let response = response_parts(
+        Response::builder()
+            .header(header::CACHE_CONTROL, "max-age=5")
+            .header(header::VARY, "sun, weather"),
+    );
+
+    let policy = CachePolicy::new(
+        &request_parts(Request::builder().header("sun", "shining").header("weather", "nice")),
+        &response,
+    );
+
+    match policy.before_request(&request_parts(Request::builder().header("sun", "shining")), now) {
+        BeforeRequest::Stale { matches, reason, .. } => {
+            assert!(!matches);
+            assert_eq!(reason.cache_status_fwd(), "vary-miss");
+            assert_eq!(
+                reason,
+                StaleReason::VaryMismatch {
+                    name: HeaderName::from_static("weather"),
+                    stored: Some(HeaderValue::from_static("nice")),
+                    presented: None,
+                }
+            );
+        },
+        _ => panic!("expected vary mismatch"),
+    }
+}
+
+fn normalized_policy(vary: &str, req: http::request::Builder) -> CachePolicy {
+    let response = response_parts(
+        Response::builder()
+            .header(header::CACHE_CONTROL, "max-age=5")
+            .header(header::VARY, vary),
+    );
+    let options = CacheOptions {
+        vary_normalizers: vec![
+            Arc::new(AcceptEncodingNormalizer::default()),
+            Arc::new(AcceptLanguageNormalizer),
+            Arc::new(DeviceClassNormalizer),
+        ],
+        ..Default::default()
+    };
+    CachePolicy::new_options(&request_parts(req), &response, SystemTime::now(), options)
+}
+
+#[test]
+fn test_normalized_accept_encoding() {
+    let now = SystemTime::now();
+    let policy = normalized_policy("accept-encoding", Request::builder().header(header::ACCEPT_ENCODING, "gzip, br"));
+
+    for matching in ["br,gzip", "br", "deflate, br;q=0.5", "*"] {
+        let req = request_parts(Request::builder().header(header::ACCEPT_ENCODING, matching));
+        assert!(policy.before_request(&req, now).satisfies_without_revalidation(), "{matching}");
+    }
+    for other in ["gzip", "br;q=0, gzip", "identity"] {
+        let req = request_parts(Request::builder().header(header::ACCEPT_ENCODING, other));
+        assert!(!policy.before_request(&req, now).satisfies_without_revalidation(), "{other}");
+    }
+
+    // neither can use a compressed response
+    let policy = normalized_policy("accept-encoding", Request::builder().header(header::ACCEPT_ENCODING, "deflate"));
+    assert!(policy.before_request(&request_parts(Request::builder()), now).satisfies_without_revalidation());
+}
+
+#[test]
+fn test_normalized_accept_language() {
+    let now = SystemTime::now();
+    let policy = normalized_policy("accept-language", Request::builder().header(header::ACCEPT_LANGUAGE, "en-US,fr;q=0.5"));
+
+    let req = request_parts(Request::builder().header(header::ACCEPT_LANGUAGE, "fr;q=0.5, en-us, de;q=0"));
+    assert!(policy.before_request(&req, now).satisfies_without_revalidation());
+    let req = request_parts(Request::builder().header(header::ACCEPT_LANGUAGE, "fr, en-US;q=0.5"));
+    assert!(!policy.before_request(&req, now).satisfies_without_revalidation());
+}
+
+#[test]
+fn test_normalized_user_agent() {
+    let now = SystemTime::now();
+    let policy = normalized_policy("user-agent", Request::builder().header(header::USER_AGENT, "Mozilla/5.0 (iPhone; CPU iPhone OS 17_0 like Mac OS X) Mobile/15E148"));
+
+    let req = request_parts(Request::builder().header(header::USER_AGENT, "Mozilla/5.0 (Linux; Android 14; Pixel 8) Mobile Safari/537.36"));
+    assert!(policy.before_request(&req, now).satisfies_without_revalidation());
+    let req = request_parts(Request::builder().header(header::USER_AGENT, "Mozilla/5.0 (X11; Linux x86_64) Firefox/130.0"));
+    assert!(!policy.before_request(&req, now).satisfies_without_revalidation());
+}
+
+#[test]
+fn test_custom_normalizer() {
+    #[derive(Debug)]
+    struct Country;
+
+    impl VaryNormalizer for Country {
+        fn header_name(&self) -> &str {
+            "x-country"
+        }
+
+        fn normalize(&self, value: Option<&str>) -> Option<String> {
+            let eu = ["DE", "FR", "PL"];
+            Some(if value.is_some_and(|c| eu.contains(&c)) { "eu" } else { "other" }.into())
+        }
+    }
+
+    let now = SystemTime::now();
+    let mut policy = normalized_policy("X-Country", Request::builder().header("x-country", "DE"));
+    let req = request_parts(Request::builder().header("x-country", "PL"));
+    assert!(!policy.before_request(&req, now).satisfies_without_revalidation());
+
+    policy.set_vary_normalizers(vec![Arc::new(Country)]);
+    assert!(policy.before_request(&req, now).satisfies_without_revalidation());
+    assert!(!policy.before_request(&request_parts(Request::builder()), now).satisfies_without_revalidation());
+}
+
+#[test]
+fn test_vary_combines_field_lines() {
+    let now = SystemTime::now();
+    let response = response_parts(
+        Response::builder()
+            .header(header::CACHE_CONTROL, "max-age=5")
+            .header(header::VARY, "Accept-Language"),
+    );
+    let policy = CachePolicy::new(
+        &request_parts(Request::builder()
+            .header(header::ACCEPT_LANGUAGE, "en")
+            .header(header::ACCEPT_LANGUAGE, "fr")),
+        &response,
+    );
+
+    let same = request_parts(Request::builder().header(header::ACCEPT_LANGUAGE, "en ,fr"));
+    assert!(policy.before_request(&same, now).satisfies_without_revalidation());
+    let same = request_parts(Request::builder().header(header::ACCEPT_LANGUAGE, "en,").header(header::ACCEPT_LANGUAGE, " fr"));
+    assert!(policy.before_request(&same, now).satisfies_without_revalidation(), "empty items are ignored");
+    let same = request_parts(Request::builder().header(header::ACCEPT_LANGUAGE, ", en,, fr ,"));
+    assert!(policy.before_request(&same, now).satisfies_without_revalidation());
+
+    // the first field line is the same, but the combined value isn't
+    let other = request_parts(Request::builder()
+        .header(header::ACCEPT_LANGUAGE, "en")
+        .header(header::ACCEPT_LANGUAGE, "de"));
+    match policy.before_request(&other, now) {
+        BeforeRequest::Stale { reason, .. } => assert_eq!(
+            reason,
+            StaleReason::VaryMismatch {
+                name: HeaderName::from_static("accept-language"),
+                stored: Some(HeaderValue::from_static("en, fr")),
+                presented: Some(HeaderValue::from_static("en, de")),
+            }
+        ),
+        _ => panic!("expected vary mismatch"),
+    }
+    let first_only = request_parts(Request::builder().header(header::ACCEPT_LANGUAGE, "en"));
+    assert!(!policy.before_request(&first_only, now).satisfies_without_revalidation());
+}
+
+#[test]
+fn test_vary_keeps_commas_in_quoted_strings() {
+    let now = SystemTime::now();
+    let response = response_parts(
+        Response::builder()
+            .header(header::CACHE_CONTROL, "max-age=5")
+            .header(header::VARY, "X-Tags"),
+    );
+    let policy = CachePolicy::new(&request_parts(Request::builder().header("x-tags", r#"a;q="x,y", b"#)), &response);
+
+    let same = request_parts(Request::builder().header("x-tags", r#"a;q="x,y",b"#));
+    assert!(policy.before_request(&same, now).satisfies_without_revalidation());
+    let other = request_parts(Request::builder().header("x-tags", r#"a;q="x, y", b"#));
+    assert!(!policy.before_request(&other, now).satisfies_without_revalidation(), "whitespace inside quotes is significant");
+    let escaped = CachePolicy::new(&request_parts(Request::builder().header("x-tags", r#""a\",b", c"#)), &response);
+    let same = request_parts(Request::builder().header("x-tags", r#""a\",b",c"#));
+    assert!(escaped.before_request(&same, now).satisfies_without_revalidation());
+}
+
+#[test]
+fn test_vary_names_case_and_duplicates() {
+    let now = SystemTime::now();
+    let response = response_parts(
+        Response::builder()
+            .header(header::CACHE_CONTROL, "max-age=5")
+            .header(header::VARY, "WEATHER, Sun")
+            .header(header::VARY, "weather"),
+    );
+    let policy = CachePolicy::new(
+        &request_parts(Request::builder().header("weather", "nice").header("sun", "shining")),
+        &response,
+    );
+
+    let same = request_parts(Request::builder().header("Weather", "nice").header("SUN", "shining"));
+    assert!(policy.before_request(&same, now).satisfies_without_revalidation());
+    let other = request_parts(Request::builder().header("weather", "nice").header("sun", "set"));
+    assert!(!policy.before_request(&other, now).satisfies_without_revalidation());
+}
+
+#[test]
+fn test_vary_star_in_any_field_line() {
+    let now = SystemTime::now();
+    let response = response_parts(
+        Response::builder()
+            .header(header::CACHE_CONTROL, "max-age=5")
+            .header(header::VARY, "weather")
+            .header(header::VARY, "*"),
+    );
+    let req = request_parts(Request::builder().header("weather", "nice"));
+    let policy = CachePolicy::new(&req, &response);
+    match policy.before_request(&req, now) {
+        BeforeRequest::Stale { reason, .. } => assert_eq!(reason, StaleReason::VaryStar),
+        _ => panic!("expected vary star"),
+    }
+}
+
+#[test]
+fn test_secondary_key() {
+    let response = response_parts(
+        Response::builder()
+            .header(header::CACHE_CONTROL, "max-age=5")
+            .header(header::VARY, "weather, Sun"),
+    );
+    let req = |weather: &str| request_parts(Request::builder().header("weather", weather).header("sun", "shining"));
+    let nice = CachePolicy::new(&req("nice"), &response);
+    let bad = CachePolicy::new(&req("bad"), &response);
+    let key = nice.secondary_key().unwrap();
+    assert_ne!(Some(key), bad.secondary_key());
+
+    let options = CacheOptions::default();
+    assert_eq!(Some(key), options.secondary_key_for_request(&req("nice"), ["sun", "WEATHER"]));
+    assert_eq!(Some(key), options.secondary_key_for_request(&req("nice"), ["weather, sun, sun"]));
+    assert_ne!(Some(key), options.secondary_key_for_request(&req("nice"), ["weather"]));
+    assert_eq!(None, options.secondary_key_for_request(&req("nice"), ["weather", "*"]));
+
+    // an absent header isn't the same as an empty one
+    let empty = request_parts(Request::builder().header("weather", "").header("sun", "shining"));
+    let absent = request_parts(Request::builder().header("sun", "shining"));
+    assert_ne!(options.secondary_key_for_request(&empty, ["weather, sun"]), options.secondary_key_for_request(&absent, ["weather, sun"]));
+
+    // stable across runs
+    let no_vary = CachePolicy::new(&req("nice"), &response_parts(Response::builder().header(header::CACHE_CONTROL, "max-age=5")));
+    assert_eq!(no_vary.secondary_key().unwrap().to_string(), "6c62272e07bb014262b821756295c58d");
+    assert_eq!(key.to_string().len(), 32);
+}
+
+#[test]
+fn test_secondary_key_is_normalized() {
+    let policy = normalized_policy("accept-encoding", Request::builder().header(header::ACCEPT_ENCODING, "gzip, br"));
+    let options = CacheOptions {
+        vary_normalizers: vec![Arc::new(AcceptEncodingNormalizer::default())],
+        ..Default::default()
+    };
+    let req = request_parts(Request::builder().header(header::ACCEPT_ENCODING, "br"));
+    assert_eq!(policy.secondary_key(), options.secondary_key_for_request(&req, ["accept-encoding"]));
+
+    let star = CachePolicy::new(&req, &response_parts(Response::builder().header(header::VARY, "*")));
+    assert_eq!(star.secondary_key(), None);
+}